@@ -0,0 +1,166 @@
+use crate::bulma::Color;
+use crate::content::ContentContext;
+use crate::markdown::markdown_to_html;
+use crate::placeholders::Placeholders;
+use crate::templates::AdmonitionTemplate;
+use askama::Template;
+
+/// The header of an admonition, like `notification tip icon=guitar Practice daily`.
+struct Header<'a> {
+    notification: bool,
+    color: Color,
+    icon: Option<&'a str>,
+    title: Option<&'a str>,
+}
+
+/// The color, icon and title used for the known kinds of admonitions.
+fn kind(name: &str) -> Option<(Color, &'static str, &'static str)> {
+    match name.to_lowercase().as_str() {
+        "note" | "info" => Some((Color::Info, "information-outline", "Note")),
+        "tip" => Some((Color::Succes, "lightbulb-on-outline", "Tip")),
+        "important" => Some((Color::Link, "alert-decagram-outline", "Important")),
+        "warning" => Some((Color::Warning, "alert-outline", "Warning")),
+        "caution" | "danger" => Some((Color::Danger, "alert-octagon-outline", "Caution")),
+        _ => None,
+    }
+}
+
+/// Parses a container header, which can start with `notification` or `message`.
+fn parse_header<'a>(header: &'a str, context: &ContentContext) -> Option<Header<'a>> {
+    let mut rest = header.trim();
+    let mut notification = false;
+    for style in ["notification", "message"] {
+        if let Some(r) = rest.strip_prefix(style) {
+            if r.is_empty() || r.starts_with(char::is_whitespace) {
+                notification = style == "notification";
+                rest = r.trim_start();
+            }
+        }
+    }
+    let (name, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    to_header(notification, name, rest, context)
+}
+
+fn to_header<'a>(
+    notification: bool,
+    name: &str,
+    rest: &'a str,
+    context: &ContentContext,
+) -> Option<Header<'a>> {
+    let (color, mut icon, mut title) = match (kind(name), Color::from_name(name)) {
+        (Some((c, i, t)), _) => (c, Some(i), Some(t)),
+        (None, Some(c)) => (c, None, None),
+        (None, None) => {
+            println!(
+                "error: Unknown admonition {} on page {}",
+                name, context.path
+            );
+            return None;
+        }
+    };
+    let mut rest = rest.trim();
+    if let Some(r) = rest.strip_prefix("icon=") {
+        let (i, r) = r.split_once(char::is_whitespace).unwrap_or((r, ""));
+        icon = Some(i.trim_start_matches("mdi-"));
+        rest = r.trim();
+    }
+    if !rest.is_empty() {
+        title = Some(rest);
+    }
+    Some(Header {
+        notification,
+        color,
+        icon,
+        title,
+    })
+}
+
+fn render(header: &Header, body: &str, context: &ContentContext) -> String {
+    AdmonitionTemplate {
+        notification: header.notification,
+        color: header.color.to_css_class(),
+        icon: header.icon,
+        title: header.title,
+        content: &markdown_to_html(body, context),
+    }
+    .render()
+    .unwrap()
+}
+
+fn fence_length(line: &str) -> usize {
+    line.len() - line.trim_start_matches(':').len()
+}
+
+/// Adds the marker for an admonition as a paragraph of its own.
+fn push_admonition(extracted: &mut Placeholders, html: String) {
+    extracted.push(html, true);
+    extracted.text.push_str("\n\n");
+}
+
+/// Replaces admonitions with markers. Both the `> [!WARNING]` blockquote syntax and the container
+/// syntax of `::: warning` until a line with the same number of colons are supported. The header
+/// can start with `notification` to use a notification instead of a message, and can set the
+/// icon with `icon=guitar`, the rest is used as title. The body is rendered as markdown, so
+/// admonitions can be nested.
+pub(crate) fn extract_admonitions(text: &str, context: &ContentContext) -> Placeholders {
+    let mut extracted = Placeholders::new("VADOSADMONITION", text.len());
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut code_fence: Option<&str> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if let Some(fence) = code_fence {
+            if trimmed.starts_with(fence) {
+                code_fence = None
+            }
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            code_fence = Some(&trimmed[..3]);
+        } else if trimmed.starts_with(":::") && !trimmed.trim_start_matches(':').is_empty() {
+            let length = fence_length(trimmed);
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim().len() == length && fence_length(l.trim()) == length);
+            match (end, parse_header(&trimmed[length..], context)) {
+                (Some(end), Some(header)) => {
+                    let body: String = lines[i + 1..i + 1 + end].concat();
+                    push_admonition(&mut extracted, render(&header, &body, context));
+                    i += end + 2;
+                    continue;
+                }
+                (None, _) => println!(
+                    "error: Admonition {} is not closed on page {}",
+                    trimmed, context.path
+                ),
+                _ => {}
+            }
+        } else if let Some(header) = trimmed
+            .strip_prefix('>')
+            .map(str::trim_start)
+            .and_then(|t| t.strip_prefix("[!"))
+        {
+            let header = header
+                .split_once(']')
+                .and_then(|(name, rest)| to_header(false, name, rest, context));
+            if let Some(header) = header {
+                let quoted = lines[i + 1..]
+                    .iter()
+                    .take_while(|l| l.trim_start().starts_with('>'))
+                    .count();
+                let body: String = lines[i + 1..i + 1 + quoted]
+                    .iter()
+                    .map(|l| {
+                        let l = l.trim_start().strip_prefix('>').unwrap_or(l);
+                        l.strip_prefix(' ').unwrap_or(l)
+                    })
+                    .collect();
+                push_admonition(&mut extracted, render(&header, &body, context));
+                i += quoted + 1;
+                continue;
+            }
+        }
+        extracted.text.push_str(line);
+        i += 1;
+    }
+    extracted
+}
@@ -0,0 +1,140 @@
+use crate::config_files::{AuthorConfig, MainConfig};
+use crate::data::SiteData;
+use crate::headings::slugify;
+use crate::structure::{Item, SocialItem, Structure};
+use crate::taxonomies::generated_item;
+use crate::templates::AuthorsTemplate;
+use askama::Template;
+use std::collections::{BTreeMap, HashMap};
+
+const AUTHORS_PATH: &str = "/authors";
+const AUTHORS_DATA: &str = "authors";
+
+/// Someone writing pages, with the key of the avatar in the images.
+#[derive(Debug)]
+pub(crate) struct Author {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) avatar: Option<String>,
+    pub(crate) bio: Option<String>,
+    pub(crate) socials: Vec<SocialItem>,
+}
+
+impl Author {
+    fn new(id: String, config: AuthorConfig) -> Author {
+        let mut socials = vec![];
+        for raw in config.socials.unwrap_or_default() {
+            match SocialItem::new(&raw) {
+                Ok(social) => socials.push(social),
+                Err(e) => println!("error: {} For author {}", e, id),
+            }
+        }
+        Author {
+            id,
+            name: config.name,
+            avatar: config.avatar,
+            bio: config.bio,
+            socials,
+        }
+    }
+    pub(crate) fn url(&self) -> String {
+        format!("{}/{}", AUTHORS_PATH, slugify(&self.id))
+    }
+}
+
+/// The authors from main.json, together with those in the `authors` site data, which can be a
+/// single file or a directory with a file for each author.
+pub(crate) fn load_authors(
+    main_config: &MainConfig,
+    site_data: &SiteData,
+) -> BTreeMap<String, Author> {
+    let mut configs: Vec<(String, AuthorConfig)> = main_config
+        .authors
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    if let Some(value) = site_data.find(AUTHORS_DATA) {
+        match serde_json::from_value::<HashMap<String, AuthorConfig>>(value.clone()) {
+            Ok(authors) => configs.extend(authors),
+            Err(e) => println!("error: Could not read the authors in the site data: {}", e),
+        }
+    }
+    let mut result = BTreeMap::new();
+    for (id, config) in configs {
+        if result.contains_key(&id) {
+            println!("error: Author {} is defined more than once", id);
+            continue;
+        }
+        result.insert(id.clone(), Author::new(id, config));
+    }
+    result
+}
+
+fn render(authors: &[&Author], profile: bool, structure: &Structure) -> String {
+    let authors: Vec<(&Author, Option<String>)> = authors
+        .iter()
+        .map(|a| {
+            let avatar = a.avatar.as_ref().and_then(|i| {
+                let image = structure.get_processed_image(i);
+                if image.is_none() {
+                    println!(
+                        "error: No image was found for the avatar of author {}",
+                        a.id
+                    );
+                }
+                image.map(|i| i.src.clone())
+            });
+            (*a, avatar)
+        })
+        .collect();
+    AuthorsTemplate {
+        authors: &authors,
+        profile,
+    }
+    .render()
+    .unwrap()
+    .trim()
+    .to_string()
+}
+
+/// The byline with the authors of the item.
+pub(crate) fn get_byline(item: &Item, structure: &Structure) -> Option<String> {
+    let authors: Vec<&Author> = item
+        .authors
+        .iter()
+        .filter_map(|id| {
+            let author = structure.get_author(id);
+            if author.is_none() {
+                println!("error: Unknown author {} on page {}", id, item.path);
+            }
+            author
+        })
+        .collect();
+    match authors.is_empty() {
+        true => None,
+        false => Some(render(&authors, false, structure)),
+    }
+}
+
+/// Creates a page listing all authors and a profile page for each author. The pages of an author are
+/// added as notifications once their stats are known.
+pub(crate) fn author_items(structure: &Structure) -> Vec<Item> {
+    let authors: Vec<&Author> = structure.get_authors().collect();
+    if authors.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![generated_item(
+        String::from(AUTHORS_PATH),
+        String::from("Authors"),
+        render(&authors, false, structure),
+        structure,
+    )];
+    for author in authors {
+        let content = render(&[author], true, structure);
+        let mut item = generated_item(author.url(), author.name.clone(), content, structure);
+        item.author_page = Some(author.id.clone());
+        result.push(item);
+    }
+    result
+}
@@ -19,6 +19,21 @@ pub(crate) enum Color {
 }
 
 impl Color {
+    pub(crate) fn from_name(name: &str) -> Option<Color> {
+        match name.to_lowercase().as_str() {
+            "white" => Some(Color::White),
+            "black" => Some(Color::Black),
+            "light" => Some(Color::Light),
+            "dark" => Some(Color::Dark),
+            "primary" => Some(Color::Primary),
+            "link" => Some(Color::Link),
+            "info" => Some(Color::Info),
+            "succes" | "success" => Some(Color::Succes),
+            "warning" => Some(Color::Warning),
+            "danger" => Some(Color::Danger),
+            _ => None,
+        }
+    }
     pub(crate) fn to_css_class(&self) -> &'static str {
         match self {
             Color::White => "is-white",
@@ -33,6 +48,20 @@ impl Color {
             Color::Danger => "is-danger",
         }
     }
+    pub(crate) fn to_hex(&self) -> &'static str {
+        match self {
+            Color::White => "#ffffff",
+            Color::Black => "#0a0a0a",
+            Color::Light => "#f5f5f5",
+            Color::Dark => "#363636",
+            Color::Primary => "#00d1b2",
+            Color::Link => "#485fc7",
+            Color::Info => "#3e8ed0",
+            Color::Succes => "#48c78e",
+            Color::Warning => "#ffe08a",
+            Color::Danger => "#f14668",
+        }
+    }
 }
 
 pub(crate) fn default_css_links() -> Vec<String> {
@@ -191,6 +220,7 @@ impl ImageRatio {
 
 pub(crate) enum ImageType {
     Main,
+    Content,
     Sub,
     Side,
 }
@@ -198,7 +228,7 @@ pub(crate) enum ImageType {
 impl ImageType {
     pub(crate) fn sizes(&self) -> &'static str {
         match self {
-            ImageType::Main => "(min-width: 1408px) 986px, (min-width: 769px) calc(75vw - 94px), calc(100vw - 64px)",
+            ImageType::Main | ImageType::Content => "(min-width: 1408px) 986px, (min-width: 769px) calc(75vw - 94px), calc(100vw - 64px)",
             ImageType::Sub => "(min-width: 1408px) 425px, (min-width: 769px) calc(37.5vw - 106px), calc(100vw - 112px)",
             ImageType::Side => "(min-width: 1408px) 318px, (min-width: 769px) calc(25vw - 94px), calc(100vw - 112px)",
         }
@@ -206,6 +236,7 @@ impl ImageType {
     pub(crate) fn decoding(&self) -> &'static str {
         match self {
             ImageType::Main => "sync",
+            ImageType::Content => "async",
             ImageType::Sub => "async",
             ImageType::Side => "async",
         }
@@ -213,6 +244,7 @@ impl ImageType {
     pub(crate) fn loading(&self) -> &'static str {
         match self {
             ImageType::Main => "eager",
+            ImageType::Content => "lazy",
             ImageType::Sub => "lazy",
             ImageType::Side => "lazy",
         }
@@ -0,0 +1,123 @@
+use crate::config_files::{CollectionConfig, PageConfig};
+use crate::content::get_file_path;
+use crate::dates::Date;
+use crate::headings::slugify;
+use crate::structure::{Item, Structure};
+use crate::tables::{read_records, to_records, to_text, Records};
+use serde_json::{Map, Value};
+use std::error::Error;
+
+fn is_file(data: &str) -> bool {
+    data.ends_with(".csv") || data.ends_with(".json")
+}
+
+fn get_records(
+    source: &str,
+    item: &Item,
+    config: &CollectionConfig,
+    structure: &Structure,
+) -> Result<Records, Box<dyn Error>> {
+    if is_file(&config.data) {
+        return read_records(&get_file_path(source, &item.path, &config.data));
+    }
+    match structure.get_data(&item.path, &config.data) {
+        Some(value) => to_records(value.clone()),
+        None => Err(format!("no site data found for {}", config.data).into()),
+    }
+}
+
+fn field(row: &Map<String, Value>, name: Option<&String>) -> Option<String> {
+    name.and_then(|n| row.get(n))
+        .map(to_text)
+        .filter(|v| !v.is_empty())
+}
+
+/// Creates a child item of the collection page for every record of the collection data.
+pub(crate) fn collection_items(
+    source: &str,
+    item: &Item,
+    config: &CollectionConfig,
+    structure: &Structure,
+) -> Vec<Item> {
+    let records = match get_records(source, item, config, structure) {
+        Ok(r) => r,
+        Err(e) => {
+            println!(
+                "error: Could not read collection {} of page {}: {}",
+                config.data, item.path, e
+            );
+            return vec![];
+        }
+    };
+    let parent = if item.path == "/" { "" } else { &item.path };
+    let mut result: Vec<Item> = vec![];
+    for (i, row) in records.rows.iter().enumerate() {
+        let title = match field(row, Some(&config.title)) {
+            Some(t) => t,
+            None => {
+                println!(
+                    "error: Record {} of collection {} has no {}",
+                    i + 1,
+                    config.data,
+                    config.title
+                );
+                continue;
+            }
+        };
+        let slug = slugify(&field(row, config.slug.as_ref()).unwrap_or_else(|| title.clone()));
+        let path = format!("{}/{}", parent, slug);
+        if result.iter().any(|r| r.path == path) || structure.has_item(&path) {
+            println!(
+                "error: Record {} of collection {} has the same url {} as another page, it is skipped",
+                i + 1,
+                config.data,
+                path
+            );
+            continue;
+        }
+        let order = field(row, config.order.as_ref()).and_then(|o| o.parse().ok());
+        let mut variables = item.content_settings.variables.clone().unwrap_or_default();
+        variables.extend(row.iter().map(|(k, v)| (k.clone(), to_text(v))));
+        let date = field(row, config.date.as_ref()).and_then(|d| match Date::try_from(d) {
+            Ok(date) => Some(date),
+            Err(e) => {
+                println!("error: {} in collection of {}", e, item.path);
+                None
+            }
+        });
+        let mut content_settings = item.content_settings.clone();
+        content_settings.variables = Some(variables);
+        let page_config = PageConfig {
+            title,
+            sub_title: field(row, config.sub_title.as_ref()),
+            image: field(row, config.image.as_ref()),
+            icon: None,
+            summary: field(row, config.summary.as_ref()),
+            content: config.content.clone(),
+            order: order.or(Some(i as u32 + 1)),
+            date,
+            updated: None,
+            sort: None,
+            sibling_navigation: None,
+            series: None,
+            side_notifications: None,
+            left_notifications: None,
+            right_notifications: None,
+            special: None,
+            tags: None,
+            categories: None,
+            authors: None,
+            collection: None,
+            listing: None,
+            content_settings,
+        };
+        let mut generated = Item::new(path, page_config);
+        generated.content_directory = item.path.clone();
+        if !is_file(&config.data) {
+            // tracks the data file as input of the generated page
+            structure.get_data(&generated.path, &config.data);
+        }
+        result.push(generated);
+    }
+    result
+}
@@ -1,6 +1,9 @@
 use crate::bulma::{default_css_links, default_js_links, vados_js, Color};
+use crate::dates::Date;
 use crate::files::write_raw;
+use pulldown_cmark::Options;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +16,17 @@ pub(crate) struct MainConfig {
     pub(crate) background_class: Option<String>,
     pub(crate) navbar_color: Option<Color>,
     pub(crate) footer_content: String,
+    pub(crate) variables: Option<HashMap<String, String>>,
+    pub(crate) markdown: Option<MarkdownConfig>,
+    pub(crate) highlighting: Option<HighlightConfig>,
+    /// Image relative to the image source, used to generate the favicons and web manifest.
+    pub(crate) favicon: Option<String>,
+    pub(crate) sanitize: Option<SanitizeConfig>,
+    /// The authors by id, more can be added in the `authors` site data.
+    pub(crate) authors: Option<HashMap<String, AuthorConfig>>,
+    /// File to write which pages use which data file to, for tooling that rebuilds pages when data
+    /// changes. It should be outside the destination.
+    pub(crate) data_inputs: Option<String>,
 }
 
 impl MainConfig {
@@ -28,6 +42,12 @@ impl MainConfig {
             Some(s) => s.to_css_class(),
         }
     }
+    pub(crate) fn get_theme_color(&self) -> &'static str {
+        match self.navbar_color.as_ref() {
+            None => Color::Warning.to_hex(),
+            Some(s) => s.to_hex(),
+        }
+    }
     pub(crate) fn get_css_links(&self) -> Vec<String> {
         match self.include_default_css {
             Some(b) if !b => self.css_files.clone(),
@@ -42,7 +62,7 @@ impl MainConfig {
         match self.include_default_js {
             Some(b) if !b => self.js_files.clone(),
             _ => {
-                write_raw(destination, default_js_links().get(0).unwrap(), vados_js());
+                write_raw(destination, default_js_links().first().unwrap(), vados_js());
                 let mut result = self.js_files.clone();
                 result.append(&mut default_js_links());
                 result
@@ -61,13 +81,32 @@ pub(crate) struct PageConfig {
     pub(crate) summary: Option<String>,
     pub(crate) content: String,
     pub(crate) order: Option<u32>,
+    pub(crate) date: Option<Date>,
+    pub(crate) updated: Option<Date>,
+    /// How the children of this page are sorted.
+    pub(crate) sort: Option<SortStrategy>,
+    /// Links to the previous and next page below the content, on by default below the top level.
+    pub(crate) sibling_navigation: Option<bool>,
+    /// Numbers the children of this page, like the parts of a course.
+    pub(crate) series: Option<bool>,
+    /// What to show in the side column, set on a section it applies to the pages below it.
+    pub(crate) side_notifications: Option<SideNotifications>,
     pub(crate) left_notifications: Option<Vec<Notification>>,
     pub(crate) right_notifications: Option<Vec<Notification>>,
+    pub(crate) special: Option<SpecialPage>,
+    pub(crate) tags: Option<Vec<String>>,
+    pub(crate) categories: Option<Vec<String>>,
+    /// The ids of the authors of the page.
+    pub(crate) authors: Option<Vec<String>>,
+    pub(crate) collection: Option<CollectionConfig>,
+    pub(crate) listing: Option<ListingConfig>,
+    #[serde(flatten)]
+    pub(crate) content_settings: ContentSettings,
 }
 
 impl PageConfig {
     pub(crate) fn new(path: &str) -> PageConfig {
-        let last = path.split('/').last().unwrap();
+        let last = path.split('/').next_back().unwrap();
         PageConfig {
             title: String::from(last),
             sub_title: None,
@@ -76,26 +115,228 @@ impl PageConfig {
             summary: None,
             content: format!("<h1>{}</h1>", last),
             order: None,
+            date: None,
+            updated: None,
+            sort: None,
+            sibling_navigation: None,
+            series: None,
+            side_notifications: None,
             left_notifications: None,
             right_notifications: None,
+            special: None,
+            tags: None,
+            categories: None,
+            authors: None,
+            collection: None,
+            listing: None,
+            content_settings: ContentSettings::default(),
+        }
+    }
+}
+
+/// Generates a child page for every record of the data, which is a key of the site data or a csv or
+/// json file relative to the page. The content is rendered for each record, with the fields of the
+/// record as variables. The other properties name the fields to use for the pages, without a slug
+/// field the title is used for the url.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionConfig {
+    pub(crate) data: String,
+    pub(crate) content: String,
+    pub(crate) title: String,
+    pub(crate) slug: Option<String>,
+    pub(crate) sub_title: Option<String>,
+    pub(crate) order: Option<String>,
+    pub(crate) summary: Option<String>,
+    pub(crate) image: Option<String>,
+    pub(crate) date: Option<String>,
+}
+
+/// Lists the children of the page below its content, split over pages of `pageSize` items. The
+/// second page is written to `<path>/page/2`.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListingConfig {
+    pub(crate) style: Option<ListingStyle>,
+    /// Sorts the children differently than the page does.
+    pub(crate) sort: Option<SortStrategy>,
+    pub(crate) descending: Option<bool>,
+    pub(crate) page_size: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+pub(crate) enum ListingStyle {
+    Cards,
+    List,
+}
+
+/// What to sort pages on. Without one, the children of a page that all have a date, like the posts
+/// of a log, are sorted newest first, other pages are sorted by order and then by title.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+pub(crate) enum SortStrategy {
+    Order,
+    Title,
+    DateAscending,
+    DateDescending,
+}
+
+/// Settings of a page that change how its content is rendered, overriding those in main.json.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContentSettings {
+    pub(crate) variables: Option<HashMap<String, String>>,
+    pub(crate) markdown: Option<MarkdownConfig>,
+    pub(crate) table_of_contents: Option<TocPosition>,
+    /// Overrides whether the content is sanitized.
+    pub(crate) sanitize: Option<bool>,
+    /// How to render a csv or json content file as table.
+    pub(crate) table: Option<TableConfig>,
+}
+
+/// Columns to show in which order, labels for the header by column, the column to sort on, and
+/// the number of decimals to show for numbers.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TableConfig {
+    pub(crate) columns: Option<Vec<String>>,
+    pub(crate) labels: Option<HashMap<String, String>>,
+    pub(crate) sort: Option<String>,
+    pub(crate) descending: Option<bool>,
+    pub(crate) decimals: Option<usize>,
+}
+
+/// Where to show the table of contents of a page.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+pub(crate) enum TocPosition {
+    Side,
+    Top,
+}
+
+/// The CommonMark extensions to enable, all of them are disabled by default.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MarkdownConfig {
+    pub(crate) tables: Option<bool>,
+    pub(crate) footnotes: Option<bool>,
+    pub(crate) strikethrough: Option<bool>,
+    pub(crate) task_lists: Option<bool>,
+    pub(crate) smart_punctuation: Option<bool>,
+    /// Adds a visible link next to each heading, headings always get an id.
+    pub(crate) heading_anchors: Option<bool>,
+    /// Renders `$...$` and `$$...$$` to MathML.
+    pub(crate) math: Option<bool>,
+}
+
+impl MarkdownConfig {
+    pub(crate) fn to_options(
+        site: Option<&MarkdownConfig>,
+        page: Option<&MarkdownConfig>,
+    ) -> Options {
+        let enabled = |get: fn(&MarkdownConfig) -> Option<bool>| {
+            page.and_then(get)
+                .or_else(|| site.and_then(get))
+                .unwrap_or(false)
+        };
+        let mut options = Options::empty();
+        if enabled(|m| m.tables) {
+            options.insert(Options::ENABLE_TABLES);
+        }
+        if enabled(|m| m.footnotes) {
+            options.insert(Options::ENABLE_FOOTNOTES);
+        }
+        if enabled(|m| m.strikethrough) {
+            options.insert(Options::ENABLE_STRIKETHROUGH);
         }
+        if enabled(|m| m.task_lists) {
+            options.insert(Options::ENABLE_TASKLISTS);
+        }
+        if enabled(|m| m.smart_punctuation) {
+            options.insert(Options::ENABLE_SMART_PUNCTUATION);
+        }
+        options
+    }
+    pub(crate) fn heading_anchors(
+        site: Option<&MarkdownConfig>,
+        page: Option<&MarkdownConfig>,
+    ) -> bool {
+        page.and_then(|m| m.heading_anchors)
+            .or_else(|| site.and_then(|m| m.heading_anchors))
+            .unwrap_or(false)
+    }
+    pub(crate) fn math(site: Option<&MarkdownConfig>, page: Option<&MarkdownConfig>) -> bool {
+        page.and_then(|m| m.math)
+            .or_else(|| site.and_then(|m| m.math))
+            .unwrap_or(false)
     }
 }
 
+/// Cleans the html the author wrote against an allowlist, the tags and attributes are allowed on top
+/// of the defaults. Pages can turn sanitizing on or off with their own `sanitize`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SanitizeConfig {
+    pub(crate) enabled: Option<bool>,
+    pub(crate) tags: Option<Vec<String>>,
+    pub(crate) attributes: Option<Vec<String>>,
+}
+
+/// Enables build time highlighting of fenced code blocks. Without inline styles the theme is
+/// written to a css file, which is added to the css links.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HighlightConfig {
+    pub(crate) theme: Option<String>,
+    pub(crate) inline_styles: Option<bool>,
+    pub(crate) line_numbers: Option<bool>,
+}
+
+/// The pages to show in the side column. `Latest` are the last of the children, or of the siblings
+/// when the page has no children. `Related` are the pages sharing the most tags, categories and
+/// links with the page. `Pages` is a list of paths.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+pub(crate) enum SideNotifications {
+    Latest,
+    Related,
+    Pages(Vec<String>),
+}
+
+/// Pages that are rendered like any other page, but are left out of the menus, breadcrumbs and
+/// side notifications. A `NotFound` page is always written to `/404.html`.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+pub(crate) enum SpecialPage {
+    NotFound,
+    Unlisted,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct RawMenuItem {
-    pub(crate) url: String,
+    pub(crate) url: Option<String>,
     pub(crate) title: Option<String>,
     pub(crate) icon: Option<String>,
+    pub(crate) children: Option<Vec<RawMenuItem>>,
+    pub(crate) auto_children: Option<usize>,
+    pub(crate) max_children: Option<usize>,
+    pub(crate) divider: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Someone writing pages, the avatar is the key of an image.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuthorConfig {
+    pub(crate) name: String,
+    pub(crate) avatar: Option<String>,
+    pub(crate) bio: Option<String>,
+    pub(crate) socials: Option<Vec<RawSocialItem>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct RawSocialItem {
     pub(crate) url: String,
     pub(crate) icon: Option<String>,
     pub(crate) color: Option<String>,
+    pub(crate) rel_me: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -125,6 +366,7 @@ pub(crate) struct ImageReference {
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct ImageList {
+    #[allow(dead_code)]
     pub(crate) title: Option<String>,
     pub(crate) list: Vec<ImageReference>,
 }
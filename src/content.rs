@@ -1,40 +1,73 @@
+use crate::authors::get_byline;
 use crate::bulma::{Color, ImageType};
-use crate::config_files::{MainConfig, MenuConfig, Notification, PageConfig};
+use crate::config_files::{
+    CollectionConfig, ContentSettings, HighlightConfig, MainConfig, MarkdownConfig, MenuConfig,
+    Notification, PageConfig, TableConfig, TocPosition,
+};
+use crate::headings::{table_of_contents, HeadingIds};
+use crate::highlight::write_highlight_css;
 use crate::image::ProcessedImage;
+use crate::links::check_links;
+use crate::markdown::markdown_to_html;
+use crate::sanitize::Sanitizer;
+use crate::shortcodes::{expand, Variables};
+use crate::siblings::get_sibling_navigation;
+use crate::stats::ContentStats;
 use crate::structure::{Item, SocialItem, Structure};
+use crate::tables::{read_records, records_to_table};
+use crate::taxonomies::tag_links;
 use crate::templates::{
     BreadcrumbsTemplate, ContentNotificationTemplate, ContentTemplate,
     ExternalNotificationTemplate, FooterTemplate, InternalImageTemplate,
     InternalNotificationTemplate, NavigationTemplate, PageTemplate, SideMenuTemplate,
 };
 use askama::Template;
-use pulldown_cmark::{html, Parser};
+use pulldown_cmark::Options;
+use std::cell::RefCell;
 use std::fs;
 use std::fs::File;
 use std::path::Path;
 use std::sync::Arc;
 
-pub(crate) struct ContentItems {
-    pub(crate) item: Item,
-    pub(crate) left_sub_notifications: Option<Vec<String>>,
-    pub(crate) right_sub_notifications: Option<Vec<String>>,
-}
-
 pub(crate) struct GenericContent {
     background_class: String,
+    socials: Vec<SocialItem>,
+    rel_me_links: Vec<String>,
+    favicon: bool,
+    theme_color: &'static str,
     css_links: Vec<String>,
     js_links: Vec<String>,
     footer: String,
 }
 
 impl GenericContent {
-    pub(crate) fn new(source: &str, destination: &str, main_config: &MainConfig) -> GenericContent {
+    pub(crate) fn new(
+        source: &str,
+        destination: &str,
+        main_config: &MainConfig,
+        menu_config: &MenuConfig,
+        structure: &Structure,
+    ) -> GenericContent {
         let background_class = main_config.get_background_class();
-        let css_links = main_config.get_css_links();
+        let socials = get_socials(menu_config);
+        let rel_me_links = menu_config
+            .socials
+            .iter()
+            .filter(|s| s.rel_me == Some(true))
+            .map(|s| s.url.clone())
+            .collect();
+        let mut css_links = main_config.get_css_links();
+        if let Some(link) = write_highlight_css(destination, main_config.highlighting.as_ref()) {
+            css_links.push(link)
+        }
         let js_links = main_config.get_js_links(destination);
-        let footer = get_footer(source, main_config);
+        let footer = get_footer(source, main_config, structure);
         GenericContent {
             background_class,
+            socials,
+            rel_me_links,
+            favicon: main_config.favicon.is_some(),
+            theme_color: main_config.get_theme_color(),
             css_links,
             js_links,
             footer,
@@ -52,7 +85,10 @@ pub(crate) struct ContentHelper<'a> {
 impl ContentHelper<'_> {
     pub(crate) fn new<'a>(path: &'a str, structure: &'a Structure) -> ContentHelper<'a> {
         let item = structure.get_item(path);
-        let side_menu = get_side_menu(path, structure);
+        let side_menu = match &item.listing_page {
+            None => get_side_menu(path, structure),
+            Some((section, _)) => get_side_menu(section, structure),
+        };
         ContentHelper {
             path,
             item,
@@ -64,22 +100,34 @@ impl ContentHelper<'_> {
         &self,
         main_config: &MainConfig,
         menu_config: &MenuConfig,
+        generic_content: &GenericContent,
     ) -> String {
         get_navigation(
             self.path,
             main_config,
             menu_config,
+            &generic_content.socials,
             self.structure,
             self.side_menu.as_ref(),
         )
     }
-    pub(crate) fn get_main_content(&self, source: &str) -> String {
-        get_main_content(source, self.path, &*self.item, self.structure)
+    pub(crate) fn get_output_path(&self) -> &str {
+        self.item.output_path()
+    }
+    pub(crate) fn get_main_content(&self, source: &str, main_config: &MainConfig) -> MainContent {
+        let context = ContentContext::new(
+            source,
+            self.path,
+            main_config,
+            Some(&self.item.content_settings),
+            self.structure,
+        );
+        get_main_content(&context, &self.item)
     }
     pub(crate) fn get_page(
         &self,
         navigation: &str,
-        main_content: &str,
+        main_content: &MainContent,
         generic_content: &GenericContent,
     ) -> String {
         let breadcrumbs = get_breadcrumbs(self.path, self.structure);
@@ -87,11 +135,12 @@ impl ContentHelper<'_> {
             navigation,
             breadcrumbs,
             side_menu: self.side_menu.as_ref(),
-            main_content,
+            toc: main_content.side_toc.as_ref(),
+            main_content: &main_content.html,
         };
         get_page(
             self.path,
-            &*self.item,
+            &self.item,
             page_helper,
             self.structure,
             generic_content,
@@ -99,21 +148,96 @@ impl ContentHelper<'_> {
     }
 }
 
+pub(crate) struct MainContent {
+    html: String,
+    side_toc: Option<String>,
+}
+
+/// Everything needed to turn a content reference of a page into html.
+pub(crate) struct ContentContext<'a> {
+    pub(crate) source: &'a str,
+    pub(crate) path: &'a str,
+    /// The directory in the source content references are relative to.
+    pub(crate) directory: String,
+    pub(crate) variables: Variables<'a>,
+    pub(crate) markdown_options: Options,
+    pub(crate) heading_anchors: bool,
+    pub(crate) math: bool,
+    pub(crate) highlighting: Option<&'a HighlightConfig>,
+    pub(crate) table_of_contents: Option<&'a TocPosition>,
+    pub(crate) structure: &'a Structure,
+    pub(crate) table: Option<&'a TableConfig>,
+    pub(crate) sanitizer: Option<Sanitizer<'a>>,
+    /// The files currently being included, to detect cycles.
+    pub(crate) includes: RefCell<Vec<String>>,
+    /// The heading ids used on the page, shared by all markdown rendered for it.
+    pub(crate) heading_ids: RefCell<HeadingIds>,
+}
+
+impl ContentContext<'_> {
+    pub(crate) fn new<'a>(
+        source: &'a str,
+        path: &'a str,
+        main_config: &'a MainConfig,
+        settings: Option<&'a ContentSettings>,
+        structure: &'a Structure,
+    ) -> ContentContext<'a> {
+        ContentContext {
+            source,
+            path,
+            directory: match structure.has_item(path) {
+                true => structure.get_item(path).content_directory.clone(),
+                false => String::from(path),
+            },
+            variables: Variables::new(main_config, settings),
+            markdown_options: MarkdownConfig::to_options(
+                main_config.markdown.as_ref(),
+                settings.and_then(|s| s.markdown.as_ref()),
+            ),
+            heading_anchors: MarkdownConfig::heading_anchors(
+                main_config.markdown.as_ref(),
+                settings.and_then(|s| s.markdown.as_ref()),
+            ),
+            math: MarkdownConfig::math(
+                main_config.markdown.as_ref(),
+                settings.and_then(|s| s.markdown.as_ref()),
+            ),
+            highlighting: main_config.highlighting.as_ref(),
+            table_of_contents: settings.and_then(|s| s.table_of_contents.as_ref()),
+            structure,
+            table: settings.and_then(|s| s.table.as_ref()),
+            sanitizer: settings
+                .and_then(|s| s.sanitize)
+                .or_else(|| main_config.sanitize.as_ref().and_then(|s| s.enabled))
+                .unwrap_or(false)
+                .then(|| Sanitizer::new(main_config.sanitize.as_ref())),
+            includes: RefCell::new(vec![]),
+            heading_ids: RefCell::new(structure.get_heading_ids(path)),
+        }
+    }
+    /// Cleans the html the author wrote when the page is sanitized. It's called before the markers
+    /// for generated markup are restored, so only the author's markup is cleaned.
+    pub(crate) fn sanitize(&self, html: &str) -> String {
+        match &self.sanitizer {
+            None => String::from(html),
+            Some(sanitizer) => sanitizer.clean(html, self.path),
+        }
+    }
+}
+
 struct PageHelper<'a> {
     navigation: &'a str,
     breadcrumbs: Option<String>,
     side_menu: Option<&'a String>,
+    toc: Option<&'a String>,
     main_content: &'a str,
 }
 
-pub(crate) fn to_content_items(
-    source: &str,
-    dir_path: String,
-    structure: &Structure,
-) -> ContentItems {
+/// The item for the directory, with the collection to generate child items from, if it has one.
+pub(crate) fn to_item(source: &str, dir_path: String) -> (Item, Option<CollectionConfig>) {
     let page_file_string = format!("{}/page.json", &dir_path);
     let page_file_path = Path::new(&page_file_string);
-    let page_config = match File::open(page_file_path) {
+    let mut page_config: PageConfig = match File::open(page_file_path) {
         Ok(f) => serde_json::from_reader(f).expect("JSON was not well-formatted"),
         Err(_) => PageConfig::new(&dir_path),
     };
@@ -121,59 +245,76 @@ pub(crate) fn to_content_items(
         root if root == source => String::from("/"),
         mut d => d.split_off(source.len()),
     };
-    let left_sub_notifications = match &page_config.left_notifications {
-        None => None,
-        Some(notifications) => {
-            let mut result = vec![];
-            for (i, notification) in notifications.iter().enumerate() {
-                let id = format!("sub-l-{}", i);
-                result.push(resolve_notification(
-                    source,
-                    &path,
-                    notification.clone(),
-                    id,
-                    structure,
-                ))
-            }
-            Some(result)
+    let collection = page_config.collection.take();
+    (Item::new(path, page_config), collection)
+}
+
+/// Renders the sub notifications of a page, this is done once all items are known, so links in
+/// them can be checked.
+pub(crate) fn add_sub_notifications(
+    source: &str,
+    path: &str,
+    main_config: &MainConfig,
+    structure: &Structure,
+) {
+    let item = structure.get_item(path);
+    let context = ContentContext::new(
+        source,
+        path,
+        main_config,
+        Some(&item.content_settings),
+        structure,
+    );
+    if let Some(notifications) = &item.left_notifications {
+        let mut result = vec![];
+        for (i, notification) in notifications.iter().enumerate() {
+            let id = format!("sub-l-{}", i);
+            result.push(resolve_notification(&context, notification.clone(), id))
         }
-    };
-    let right_sub_notifications = match &page_config.right_notifications {
-        None => None,
-        Some(notifications) => {
-            let mut result = vec![];
-            for (i, notification) in notifications.iter().enumerate() {
-                let id = format!("sub-r-{}", i);
-                result.push(resolve_notification(
-                    source,
-                    &path,
-                    notification.clone(),
-                    id,
-                    structure,
-                ))
-            }
-            Some(result)
+        structure.add_left_sub_notifications(path, result)
+    }
+    if let Some(notifications) = &item.right_notifications {
+        let mut result = vec![];
+        for (i, notification) in notifications.iter().enumerate() {
+            let id = format!("sub-r-{}", i);
+            result.push(resolve_notification(&context, notification.clone(), id))
         }
-    };
-    let item = Item::new(path, page_config);
-    ContentItems {
-        item,
-        left_sub_notifications,
-        right_sub_notifications,
+        structure.add_right_sub_notifications(path, result)
+    }
+    if let Some((taxonomy, term)) = &item.taxonomy_term {
+        let items = structure.get_term_items(*taxonomy, term);
+        add_item_sub_notifications(path, items, "sub-t", structure)
+    }
+    if let Some(author) = &item.author_page {
+        let items = structure.get_author_items(author);
+        add_item_sub_notifications(path, items, "sub-a", structure)
     }
 }
 
-fn resolve_notification(
-    source: &str,
+/// Adds the items as sub notifications, alternating between left and right.
+fn add_item_sub_notifications(
     path: &str,
+    items: Vec<Arc<Item>>,
+    id_prefix: &str,
+    structure: &Structure,
+) {
+    let (left, right): (Vec<_>, Vec<_>) = items_to_side_notifications(items, id_prefix, structure)
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| i % 2 == 0);
+    structure.add_left_sub_notifications(path, left.into_iter().map(|(_, n)| n).collect());
+    structure.add_right_sub_notifications(path, right.into_iter().map(|(_, n)| n).collect());
+}
+
+fn resolve_notification(
+    context: &ContentContext,
     notification: Notification,
     id: String,
-    structure: &Structure,
 ) -> String {
-    let content = get_content(source, path, &*notification.content);
+    let content = get_content(context, &notification.content);
     let image = notification
         .image
-        .and_then(|i| structure.process_image(&i, ImageType::Sub));
+        .and_then(|i| context.structure.process_image(&i, ImageType::Sub));
     match notification.url {
         None => {
             let color = notification.color.unwrap_or(Color::Info).to_css_class();
@@ -192,10 +333,16 @@ fn resolve_notification(
             InternalNotificationTemplate {
                 title: &notification.title,
                 sub_title: &None,
+                date: None,
+                reading_time: context
+                    .structure
+                    .get_content_stats(internal.split('#').next().unwrap_or(""))
+                    .and_then(|s| s.reading_time()),
                 color,
-                url: &*internal,
+                url: &internal,
                 image,
                 content: Some(content),
+                summary: None,
                 id,
             }
             .render()
@@ -206,7 +353,7 @@ fn resolve_notification(
             ExternalNotificationTemplate {
                 title: &notification.title,
                 color,
-                url: &*external,
+                url: &external,
                 image,
                 content,
                 id,
@@ -219,23 +366,30 @@ fn resolve_notification(
 
 pub(crate) fn items_to_side_notifications(
     items: Vec<Arc<Item>>,
+    id_prefix: &str,
     structure: &Structure,
 ) -> Vec<String> {
     let mut result = vec![];
     for (i, item) in items.iter().enumerate() {
-        let id = format!("sub-s-{}", i);
+        let id = format!("{}-{}", id_prefix, i);
         let color = Color::Info.to_css_class();
         let image = item
             .image
             .as_ref()
             .and_then(|i| structure.process_image(i, ImageType::Side));
+        let summary = structure.get_summary(item);
         let notification = InternalNotificationTemplate {
             title: &Some(item.title.clone()),
             sub_title: &item.sub_title,
+            date: item.date,
+            reading_time: structure
+                .get_content_stats(&item.path)
+                .and_then(|s| s.reading_time()),
             color,
-            url: &*item.path,
+            url: &item.path,
             image,
             content: None,
+            summary: summary.as_deref(),
             id,
         }
         .render()
@@ -245,7 +399,7 @@ pub(crate) fn items_to_side_notifications(
     result
 }
 
-fn get_file_path(source: &str, path: &str, reference: &str) -> String {
+pub(crate) fn get_file_path(source: &str, path: &str, reference: &str) -> String {
     if path == "/" {
         format!("{}/{}", source, reference)
     } else {
@@ -253,13 +407,11 @@ fn get_file_path(source: &str, path: &str, reference: &str) -> String {
     }
 }
 
-fn md_to_content(file_path: &str) -> String {
+fn md_to_content(context: &ContentContext, file_path: &str) -> String {
     match fs::read_to_string(file_path) {
         Ok(text) => {
-            let mut html_output: String = String::with_capacity(text.len() * 3 / 2);
-            let parser = Parser::new(&*text);
-            html::push_html(&mut html_output, parser);
-            html_output
+            let expanded = expand(&text, context, file_path);
+            expanded.restore(&markdown_to_html(&expanded.text, context))
         }
         Err(e) => {
             println!(
@@ -271,9 +423,12 @@ fn md_to_content(file_path: &str) -> String {
     }
 }
 
-fn html_to_content(file_path: &str) -> String {
+fn html_to_content(context: &ContentContext, file_path: &str) -> String {
     match fs::read_to_string(file_path) {
-        Ok(text) => text,
+        Ok(text) => {
+            let expanded = expand(&text, context, file_path);
+            expanded.restore(&context.sanitize(&expanded.text))
+        }
         Err(e) => {
             println!(
                 "There was an error reading Html content from path {}.\n{}",
@@ -284,21 +439,43 @@ fn html_to_content(file_path: &str) -> String {
     }
 }
 
-fn get_content(source: &str, path: &str, reference: &str) -> String {
-    let file_path = get_file_path(source, path, reference);
-    match reference {
-        md if md.ends_with(".md") => md_to_content(&file_path),
-        html if html.ends_with(".html") => html_to_content(&file_path),
-        raw if raw.ends_with('>') => String::from(raw),
-        _ => panic!(
-            "Can't handle content reference that looks like: {}",
-            reference
-        ),
-    }
+fn get_content(context: &ContentContext, reference: &str) -> String {
+    let file_path = get_file_path(context.source, &context.directory, reference);
+    let html = match reference {
+        md if md.ends_with(".md") => md_to_content(context, &file_path),
+        html if html.ends_with(".html") => html_to_content(context, &file_path),
+        data if data.ends_with(".csv") || data.ends_with(".json") => {
+            let table = context.table;
+            match read_records(&file_path).and_then(|r| records_to_table(r, table)) {
+                Ok(html) => html,
+                Err(e) => {
+                    println!(
+                        "error: Could not render {} as table on page {}: {}",
+                        file_path, context.path, e
+                    );
+                    String::new()
+                }
+            }
+        }
+        "" => String::new(),
+        raw if raw.ends_with('>') => {
+            let expanded = expand(raw, context, context.path);
+            expanded.restore(&context.sanitize(&expanded.text))
+        }
+        _ => {
+            println!(
+                "error: Can't handle content reference {} on page {}, only md, html, csv and json files or html are supported",
+                reference, context.path
+            );
+            String::new()
+        }
+    };
+    check_links(&html, context)
 }
 
-fn get_footer(source: &str, main_config: &MainConfig) -> String {
-    let footer_content = get_content(source, "/", &main_config.footer_content);
+fn get_footer(source: &str, main_config: &MainConfig, structure: &Structure) -> String {
+    let context = ContentContext::new(source, "/", main_config, None, structure);
+    let footer_content = get_content(&context, &main_config.footer_content);
     FooterTemplate {
         content: &footer_content,
     }
@@ -309,14 +486,15 @@ fn get_footer(source: &str, main_config: &MainConfig) -> String {
 pub(crate) fn to_internal_image(
     processed_image: Arc<ProcessedImage>,
     image_type: ImageType,
+    alt: Option<&str>,
 ) -> String {
     InternalImageTemplate {
         ratio: processed_image.ratio.to_css_class(),
-        title: &*processed_image.title,
+        title: &processed_image.title,
         image_type,
-        src: &*processed_image.src,
-        srcset: &*processed_image.srcset,
-        alt: &*processed_image.alt,
+        src: &processed_image.src,
+        srcset: &processed_image.srcset,
+        alt: alt.unwrap_or(&processed_image.alt),
     }
     .render()
     .unwrap()
@@ -327,7 +505,7 @@ fn get_side_menu(path: &str, structure: &Structure) -> Option<String> {
         None => None,
         Some(menu_item) => {
             let s = SideMenuTemplate {
-                path: &*path,
+                path,
                 menu_item: &menu_item,
             };
             Some(s.render().unwrap())
@@ -335,19 +513,31 @@ fn get_side_menu(path: &str, structure: &Structure) -> Option<String> {
     }
 }
 
+fn get_socials(menu_config: &MenuConfig) -> Vec<SocialItem> {
+    let mut result = vec![];
+    for raw in &menu_config.socials {
+        match SocialItem::new(raw) {
+            Ok(social) => result.push(social),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    result
+}
+
 fn get_navigation(
     path: &str,
     main_config: &MainConfig,
     menu_config: &MenuConfig,
+    socials: &Vec<SocialItem>,
     structure: &Structure,
     side_menu: Option<&String>,
 ) -> String {
     NavigationTemplate {
         path,
-        site_title: &*main_config.site_title,
+        site_title: &main_config.site_title,
         color: main_config.get_navbar_color(),
         main_menu: &structure.get_main_menu_items(menu_config),
-        socials: &menu_config.socials.iter().map(SocialItem::new).collect(),
+        socials,
         side_menu,
     }
     .render()
@@ -365,19 +555,43 @@ fn get_breadcrumbs(path: &str, structure: &Structure) -> Option<String> {
     })
 }
 
-fn get_main_content(source: &str, path: &str, item: &Item, structure: &Structure) -> String {
+fn get_main_content(context: &ContentContext, item: &Item) -> MainContent {
     let image = match &item.image {
         None => None,
-        Some(i) => structure.process_image(i, ImageType::Main),
+        Some(i) => context.structure.process_image(i, ImageType::Main),
     };
-    ContentTemplate {
-        title: &*item.title,
+    let content = get_content(context, &item.content);
+    // the sub notifications are rendered later, and continue with the ids of the main content
+    context
+        .structure
+        .add_heading_ids(&item.path, context.heading_ids.take());
+    let stats = ContentStats::new(&content);
+    let reading_time = stats.reading_time();
+    context.structure.add_content_stats(&item.path, stats);
+    let toc = context
+        .table_of_contents
+        .and_then(|position| table_of_contents(&content).map(|toc| (position, toc)));
+    let (top_toc, side_toc) = match toc {
+        None => (None, None),
+        Some((TocPosition::Top, toc)) => (Some(toc), None),
+        Some((TocPosition::Side, toc)) => (None, Some(toc)),
+    };
+    let html = ContentTemplate {
+        title: &item.title,
         sub_title: &item.sub_title,
+        tags: &tag_links(item),
+        byline: get_byline(item, context.structure),
+        date: item.date,
+        updated: item.updated.filter(|u| Some(*u) != item.date),
+        reading_time,
         image,
-        content: get_content(source, path, &item.content),
+        toc: top_toc,
+        content,
+        siblings: get_sibling_navigation(item, context.structure),
     }
     .render()
-    .unwrap()
+    .unwrap();
+    MainContent { html, side_toc }
 }
 
 fn get_page(
@@ -388,16 +602,25 @@ fn get_page(
     generic_content: &GenericContent,
 ) -> String {
     PageTemplate {
-        title: &*item.title,
-        summary: &item.summary,
+        title: &item.title,
+        summary: &structure.get_summary(item),
+        reading_time: structure
+            .get_content_stats(path)
+            .and_then(|s| s.reading_time()),
+        no_index: item.is_not_found(),
         background_class: &generic_content.background_class,
         navigation: page_helper.navigation,
         breadcrumbs: page_helper.breadcrumbs,
         side_menu: page_helper.side_menu,
+        toc: page_helper.toc,
         main_content: page_helper.main_content,
-        left_sub_notifications: &*structure.get_left_sub_notifications(path),
-        right_sub_notifications: &*structure.get_right_sub_notifications(path),
+        listing: structure.get_listing(path).as_deref().map(|l| l.as_str()),
+        left_sub_notifications: &structure.get_left_sub_notifications(path),
+        right_sub_notifications: &structure.get_right_sub_notifications(path),
         side_notifications: &structure.get_side_notifications(path),
+        rel_me_links: &generic_content.rel_me_links,
+        favicon: generic_content.favicon,
+        theme_color: generic_content.theme_color,
         footer: &generic_content.footer,
         css_links: &generic_content.css_links,
         js_links: &generic_content.js_links,
@@ -0,0 +1,130 @@
+use crate::config_files::MainConfig;
+use crate::files::DATA_DIRECTORY;
+use crate::structure::Structure;
+use crate::tables::read_csv;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use walkdir::WalkDir;
+
+/// The data files of the site, in a tree where directories and files are keys, so `tour/dates.csv`
+/// is found as `tour.dates`.
+#[derive(Debug, Default)]
+pub(crate) struct SiteData {
+    tree: Map<String, Value>,
+    /// The data file for the keys in the tree that are a whole file.
+    files: HashMap<String, String>,
+}
+
+fn read_data_file(file_path: &str) -> Result<Option<Value>, Box<dyn Error>> {
+    let value = match file_path.rsplit_once('.').map(|(_, e)| e) {
+        Some("json") => serde_json::from_str(&fs::read_to_string(file_path)?)?,
+        Some("toml") => toml::from_str(&fs::read_to_string(file_path)?)?,
+        Some("csv") => {
+            let records = read_csv(file_path)?;
+            Value::Array(records.rows.into_iter().map(Value::Object).collect())
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+impl SiteData {
+    /// Loads all json, toml and csv files in the data directory of the source.
+    pub(crate) fn load(source: &str) -> SiteData {
+        let mut data = SiteData::default();
+        let data_path = format!("{}/{}", source, DATA_DIRECTORY);
+        for entry in WalkDir::new(&data_path).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let file_path = entry.path().display().to_string();
+            let value = match read_data_file(&file_path) {
+                Ok(Some(v)) => v,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!("error: could not read data file {}: {}", file_path, e);
+                    continue;
+                }
+            };
+            let relative = &file_path[data_path.len() + 1..];
+            let without_extension = relative.rsplit_once('.').map_or(relative, |(r, _)| r);
+            let keys: Vec<&str> = without_extension.split('/').collect();
+            if data.insert(&keys, value) {
+                let input = format!("{}/{}", DATA_DIRECTORY, relative);
+                data.files.insert(keys.join("."), input);
+            } else {
+                println!(
+                    "error: data file {} conflicts with another data file",
+                    file_path
+                );
+            }
+        }
+        data
+    }
+    fn insert(&mut self, keys: &[&str], value: Value) -> bool {
+        let mut node = &mut self.tree;
+        for key in &keys[..keys.len() - 1] {
+            node = match node
+                .entry(String::from(*key))
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(m) => m,
+                _ => return false,
+            };
+        }
+        node.insert(String::from(keys[keys.len() - 1]), value)
+            .is_none()
+    }
+    /// Finds the value for a dotted key, which can also be a directory.
+    pub(crate) fn find(&self, key: &str) -> Option<&Value> {
+        let mut value: Option<&Value> = None;
+        for part in key.split('.') {
+            let node = match value {
+                None => &self.tree,
+                Some(Value::Object(m)) => m,
+                Some(_) => return None,
+            };
+            value = Some(node.get(part)?);
+        }
+        value
+    }
+    /// Finds the value for a dotted key, together with the files it's from: the file containing the
+    /// key, or all files below it when the key is a directory.
+    pub(crate) fn get(&self, key: &str) -> Option<(&Value, Vec<&str>)> {
+        let value = self.find(key)?;
+        let containing = key
+            .match_indices('.')
+            .map(|(i, _)| &key[..i])
+            .chain(std::iter::once(key))
+            .find_map(|prefix| self.files.get(prefix));
+        let files = match containing {
+            Some(file) => vec![file.as_str()],
+            None => {
+                let directory = format!("{}.", key);
+                let mut files: Vec<&str> = self
+                    .files
+                    .iter()
+                    .filter(|(k, _)| k.starts_with(&directory))
+                    .map(|(_, f)| f.as_str())
+                    .collect();
+                files.sort_unstable();
+                files
+            }
+        };
+        Some((value, files))
+    }
+}
+
+/// Writes which pages use which data file to the file set in the main config, so tooling can tell
+/// which pages need to be rebuilt when a data file changes. Nothing is written into the destination,
+/// as it would be published with the site.
+pub(crate) fn write_data_inputs(main_config: &MainConfig, structure: &Structure) {
+    if let Some(file) = &main_config.data_inputs {
+        let inputs = serde_json::to_string_pretty(&structure.get_data_inputs()).unwrap();
+        if let Err(e) = fs::write(file, inputs) {
+            println!("error: could not write data inputs to {}: {}", file, e);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A calendar date, written as `YYYY-MM-DD` in the config, a time after it is ignored.
+#[derive(Debug, Deserialize, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+#[serde(try_from = "String")]
+pub(crate) struct Date {
+    year: u32,
+    month: u32,
+    day: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct DateError {
+    value: String,
+}
+
+impl Display for DateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid date, use YYYY-MM-DD", self.value)
+    }
+}
+
+impl Error for DateError {}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = DateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let error = || DateError {
+            value: value.clone(),
+        };
+        let date = value.split(['T', ' ']).next().unwrap_or_default();
+        let parts: Vec<u32> = date
+            .split('-')
+            .map(|p| p.parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| error())?;
+        match parts[..] {
+            [year, month, day]
+                if (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month) =>
+            {
+                Ok(Date { year, month, day })
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Date {
+    /// The date as used in html, like in the datetime attribute.
+    pub(crate) fn iso(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, {}",
+            MONTHS[self.month as usize - 1],
+            self.day,
+            self.year
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> Result<Date, DateError> {
+        Date::try_from(String::from(value))
+    }
+
+    #[test]
+    fn parses_dates_and_ignores_the_time() {
+        assert_eq!(date("2024-03-09").unwrap().iso(), "2024-03-09");
+        assert_eq!(date("2024-03-09T10:15:00Z").unwrap().iso(), "2024-03-09");
+        assert_eq!(date("2024-03-09 10:15").unwrap().iso(), "2024-03-09");
+        assert_eq!(date("2024-3-9").unwrap().iso(), "2024-03-09");
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for value in [
+            "",
+            "2024",
+            "2024-03",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-04-31",
+            "2024-01-00",
+            "2024-01-01-01",
+            "march 9",
+            "2024-0x-01",
+        ] {
+            assert!(date(value).is_err(), "{} should be invalid", value);
+        }
+    }
+
+    #[test]
+    fn knows_leap_years() {
+        assert!(date("2024-02-29").is_ok());
+        assert!(date("2000-02-29").is_ok());
+        assert!(date("2023-02-29").is_err());
+        assert!(date("1900-02-29").is_err());
+        assert!(date("2023-02-28").is_ok());
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+    }
+
+    #[test]
+    fn displays_and_orders_dates() {
+        assert_eq!(date("2024-01-05").unwrap().to_string(), "January 5, 2024");
+        assert!(date("2023-12-31").unwrap() < date("2024-01-01").unwrap());
+        assert!(date("2024-02-01").unwrap() > date("2024-01-31").unwrap());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::config_files::MainConfig;
+use crate::files::write_raw;
+use fast_image_resize::{FilterType, Image, MulDiv, PixelType, ResizeAlg, Resizer};
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::codecs::png::PngEncoder;
+use image::io::Reader;
+use image::{ColorType, ImageEncoder};
+use serde::Serialize;
+use std::error::Error;
+use std::num::NonZeroU32;
+
+const ICO_SIZES: [u32; 3] = [16, 32, 48];
+
+const PNG_ICONS: [(&str, u32); 5] = [
+    ("/favicon-16x16.png", 16),
+    ("/favicon-32x32.png", 32),
+    ("/apple-touch-icon.png", 180),
+    ("/android-chrome-192x192.png", 192),
+    ("/android-chrome-512x512.png", 512),
+];
+
+#[derive(Serialize)]
+struct WebManifest<'a> {
+    name: &'a str,
+    short_name: &'a str,
+    icons: Vec<ManifestIcon>,
+    theme_color: &'a str,
+    background_color: &'a str,
+    display: &'a str,
+}
+
+#[derive(Serialize)]
+struct ManifestIcon {
+    src: String,
+    sizes: String,
+    #[serde(rename = "type")]
+    mime_type: String,
+}
+
+/// Writes `favicon.ico`, the png icons and `site.webmanifest` to the root of the destination.
+pub(crate) fn generate_favicons(
+    img_source: &str,
+    destination: &str,
+    favicon: &str,
+    main_config: &MainConfig,
+) {
+    if let Err(e) = write_favicons(img_source, destination, favicon, main_config) {
+        println!("error: could not generate favicons from {}: {}", favicon, e)
+    }
+}
+
+fn write_favicons(
+    img_source: &str,
+    destination: &str,
+    favicon: &str,
+    main_config: &MainConfig,
+) -> Result<(), Box<dyn Error>> {
+    let path = format!("{}/{}", img_source, favicon);
+    let origin = Reader::open(&path)?.decode()?;
+    let side = origin.width().min(origin.height());
+    let x = (origin.width() - side) / 2;
+    let y = (origin.height() - side) / 2;
+    let square = origin.crop_imm(x, y, side, side).to_rgba8().into_raw();
+    let mut resizer = Resizer::new(ResizeAlg::Convolution(FilterType::Lanczos3));
+
+    let mut frames = vec![];
+    for size in ICO_SIZES {
+        let png = resize_to_png(&mut resizer, &square, side, size)?;
+        frames.push(IcoFrame::with_encoded(png, size, size, ColorType::Rgba8)?);
+    }
+    let mut ico = vec![];
+    IcoEncoder::new(&mut ico).encode_images(&frames)?;
+    write_raw(destination, "/favicon.ico", ico);
+
+    let mut icons = vec![];
+    for (path, size) in PNG_ICONS {
+        let png = resize_to_png(&mut resizer, &square, side, size)?;
+        write_raw(destination, path, png);
+        if path.starts_with("/android-chrome") {
+            icons.push(ManifestIcon {
+                src: String::from(path),
+                sizes: format!("{}x{}", size, size),
+                mime_type: String::from("image/png"),
+            })
+        }
+    }
+
+    let manifest = WebManifest {
+        name: &main_config.site_title,
+        short_name: &main_config.site_title,
+        icons,
+        theme_color: main_config.get_theme_color(),
+        background_color: "#ffffff",
+        display: "standalone",
+    };
+    write_raw(
+        destination,
+        "/site.webmanifest",
+        serde_json::to_string_pretty(&manifest)?,
+    );
+    Ok(())
+}
+
+fn resize_to_png(
+    resizer: &mut Resizer,
+    square: &[u8],
+    side: u32,
+    size: u32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut src_image = Image::from_vec_u8(
+        NonZeroU32::new(side).unwrap(),
+        NonZeroU32::new(side).unwrap(),
+        square.to_vec(),
+        PixelType::U8x4,
+    )?;
+    let alpha_mul_div = MulDiv::default();
+    alpha_mul_div.multiply_alpha_inplace(&mut src_image.view_mut())?;
+    let dst_size = NonZeroU32::new(size).unwrap();
+    let mut dst_image = Image::new(dst_size, dst_size, src_image.pixel_type());
+    let mut dst_view = dst_image.view_mut();
+    resizer.resize(&src_image.view(), &mut dst_view)?;
+    alpha_mul_div.divide_alpha_inplace(&mut dst_view)?;
+
+    let mut png = vec![];
+    PngEncoder::new(&mut png).write_image(dst_image.buffer(), size, size, ColorType::Rgba8)?;
+    Ok(png)
+}
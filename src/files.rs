@@ -1,9 +1,29 @@
 use crate::config_files::{ImageList, MainConfig, MenuConfig};
+use std::collections::BTreeSet;
 use std::fs;
 use std::fs::File;
 use std::path::Path;
+use std::sync::Mutex;
 use walkdir::WalkDir;
 
+/// The files of the current build, relative to the destination, so files left by earlier builds
+/// aren't taken for output.
+static WRITTEN: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Starts a new build, forgetting the files written before.
+pub(crate) fn clear_written() {
+    WRITTEN.lock().unwrap().clear()
+}
+
+/// Records a file as output of the current build, also for files that were already up to date.
+pub(crate) fn record_written(path: &str) {
+    WRITTEN.lock().unwrap().insert(String::from(path));
+}
+
+pub(crate) fn written_files() -> BTreeSet<String> {
+    WRITTEN.lock().unwrap().clone()
+}
+
 fn get_html_destination(destination: &str, path: &str) -> (String, String) {
     if path == "/" {
         (String::from(destination), String::from("index.html"))
@@ -56,6 +76,10 @@ pub(crate) fn get_image_list(source: &str) -> Option<ImageList> {
 }
 
 pub(crate) fn write_html(destination: &str, path: &str, html: &str) {
+    match path {
+        "/" => record_written("/index.html"),
+        p => record_written(&format!("{}.html", p)),
+    }
     let (path, file) = get_html_destination(destination, path);
     let contents = minifier::html::minify(html);
     fs::create_dir_all(&path).unwrap();
@@ -63,6 +87,7 @@ pub(crate) fn write_html(destination: &str, path: &str, html: &str) {
 }
 
 pub(crate) fn write_raw<C: AsRef<[u8]>>(destination: &str, path: &str, contents: C) {
+    record_written(path);
     let (path, file) = get_destination(destination, path);
     fs::create_dir_all(&path).unwrap();
     fs::write(format!("{}/{}", &path, file), contents).expect("Unable to write file");
@@ -73,6 +98,26 @@ pub(crate) fn empty(destination: &str, path: &str) -> bool {
     !Path::new(&full_path).exists()
 }
 
+/// Directory in the source with fragments that can be included in content, it's not a page.
+pub(crate) const PARTIALS_DIRECTORY: &str = "partials";
+/// Directory in the source with the data files of the site, it's not a page.
+pub(crate) const DATA_DIRECTORY: &str = "data";
+
+/// All directories in the source that are pages, so without the partials and data.
+pub(crate) fn get_page_directory_paths(source: &str) -> Vec<String> {
+    let excluded = [
+        format!("{}/{}", source, PARTIALS_DIRECTORY),
+        format!("{}/{}", source, DATA_DIRECTORY),
+    ];
+    WalkDir::new(source)
+        .into_iter()
+        .filter_entry(|e| {
+            e.metadata().unwrap().is_dir() && !excluded.iter().any(|x| e.path() == Path::new(x))
+        })
+        .map(|e| e.unwrap().path().display().to_string())
+        .collect()
+}
+
 pub(crate) fn get_all_directory_paths(source: &str) -> Vec<String> {
     WalkDir::new(source)
         .into_iter()
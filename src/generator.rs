@@ -1,15 +1,24 @@
 extern crate core;
 
-use crate::content::{to_content_items, ContentHelper, GenericContent};
+use crate::authors::{author_items, load_authors};
+use crate::collections::collection_items;
+use crate::content::{add_sub_notifications, to_item, ContentHelper, GenericContent, MainContent};
+use crate::data::{write_data_inputs, SiteData};
+use crate::favicon::generate_favicons;
 use crate::files::{
-    get_all_directory_paths, get_image_list, get_main_config, get_menu_config, write_html,
+    clear_written, get_all_directory_paths, get_image_list, get_main_config, get_menu_config,
+    get_page_directory_paths, write_html,
 };
 use crate::image::ImageProcessor;
+use crate::listings::{add_listing, listing_items};
 use crate::structure::Structure;
+use crate::taxonomies::taxonomy_items;
+use crate::verify::verify_output;
 
 /// The core function to call, if the files at the source are valid, the static site will be
 /// generated at the destination location. Please make sure the files and/or directories have the proper ownership.
 pub fn generate(source: &str, img_source: &str, destination: &str) {
+    clear_written();
     let main_config = get_main_config(source);
     let menu_config = get_menu_config(source);
 
@@ -20,30 +29,66 @@ pub fn generate(source: &str, img_source: &str, destination: &str) {
         }
     }
 
-    let structure = Structure::new(image_processor.meta_cache);
+    if let Some(favicon) = &main_config.favicon {
+        generate_favicons(img_source, destination, favicon, &main_config);
+    }
+
+    let site_data = SiteData::load(source);
+    let authors = load_authors(&main_config, &site_data);
+    let structure = Structure::new(image_processor.meta_cache, site_data, authors);
     let mut all_paths = vec![];
-    for directory_path in get_all_directory_paths(source) {
-        let content_items = to_content_items(source, directory_path, &structure);
-        let path = content_items.item.path.clone();
-        if let Some(notifications) = content_items.left_sub_notifications {
-            structure.add_left_sub_notifications(&path, notifications)
+    let mut collections = vec![];
+    for directory_path in get_page_directory_paths(source) {
+        let (item, collection) = to_item(source, directory_path);
+        if let Some(c) = collection {
+            collections.push((item.path.clone(), c));
         }
-        if let Some(notifications) = content_items.right_sub_notifications {
-            structure.add_right_sub_notifications(&path, notifications)
+        all_paths.push(item.path.clone());
+        structure.add_item(item);
+    }
+    // generated after all directories are known, so a record can't take the url of a directory
+    for (path, collection) in collections {
+        let item = structure.get_item(&path);
+        for generated in collection_items(source, &item, &collection, &structure) {
+            all_paths.push(generated.path.clone());
+            structure.add_item(generated);
         }
-        structure.add_item(content_items.item);
-        all_paths.push(path)
+    }
+    for item in taxonomy_items(&structure) {
+        all_paths.push(item.path.clone());
+        structure.add_item(item);
+    }
+    for item in author_items(&structure) {
+        all_paths.push(item.path.clone());
+        structure.add_item(item);
+    }
+    for item in listing_items(&structure) {
+        all_paths.push(item.path.clone());
+        structure.add_item(item);
     }
     structure.sort();
-
-    let generic_content = GenericContent::new(source, destination, &main_config);
+    // the main content is rendered first, so the stats of all pages are known for the notifications
+    let main_contents: Vec<MainContent> = all_paths
+        .iter()
+        .map(|path| ContentHelper::new(path, &structure).get_main_content(source, &main_config))
+        .collect();
     for path in &all_paths {
+        add_sub_notifications(source, path, &main_config, &structure);
+        add_listing(path, &structure);
+    }
+
+    let generic_content =
+        GenericContent::new(source, destination, &main_config, &menu_config, &structure);
+    for (path, main_content) in all_paths.iter().zip(main_contents) {
         let content_helper = ContentHelper::new(path, &structure);
-        let navigation = content_helper.get_navigation(&main_config, &menu_config);
-        let main_content = content_helper.get_main_content(source);
+        let navigation =
+            content_helper.get_navigation(&main_config, &menu_config, &generic_content);
         let page = content_helper.get_page(&navigation, &main_content, &generic_content);
 
         //generating the end html and writing it to file
-        write_html(destination, path, &page);
+        write_html(destination, content_helper.get_output_path(), &page);
     }
+
+    write_data_inputs(&main_config, &structure);
+    verify_output(destination);
 }
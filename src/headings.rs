@@ -0,0 +1,242 @@
+use crate::templates::TableOfContentsTemplate;
+use askama::Template;
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::collections::HashMap;
+
+/// Keeps track of the ids used in a document, so every heading gets a unique one.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct HeadingIds {
+    used: HashMap<String, usize>,
+}
+
+impl HeadingIds {
+    /// The slug itself the first time, after that with the first number that gives an unused id.
+    fn unique(&mut self, slug: String) -> String {
+        let mut count = match self.used.get(&slug) {
+            None => {
+                self.used.insert(slug.clone(), 0);
+                return slug;
+            }
+            Some(count) => *count,
+        };
+        let id = loop {
+            count += 1;
+            let id = format!("{}-{}", slug, count);
+            if !self.used.contains_key(&id) {
+                break id;
+            }
+        };
+        self.used.insert(slug, count);
+        self.used.insert(id.clone(), 0);
+        id
+    }
+}
+
+pub(crate) fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        String::from("section")
+    } else {
+        String::from(slug)
+    }
+}
+
+/// Gives all headings an id based on their text, and optionally adds a link to the heading itself.
+pub(crate) fn add_heading_ids<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    ids: &mut HeadingIds,
+    anchors: bool,
+) -> Vec<Event<'a>> {
+    let mut result = vec![];
+    let mut heading: Option<(usize, Option<String>, Vec<Event<'a>>)> = None;
+    for event in events {
+        match (&mut heading, event) {
+            (None, Event::Start(Tag::Heading(level, id, _))) => {
+                heading = Some((level as usize, id.map(String::from), vec![]))
+            }
+            (Some((level, id, inner)), Event::End(Tag::Heading(..))) => {
+                let text: String = inner
+                    .iter()
+                    .filter_map(|e| match e {
+                        Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+                        _ => None,
+                    })
+                    .collect();
+                let id = ids.unique(id.take().unwrap_or_else(|| slugify(&text)));
+                result.push(Event::Html(CowStr::from(format!(
+                    "<h{} id=\"{}\">",
+                    level, id
+                ))));
+                result.append(inner);
+                if anchors {
+                    result.push(Event::Html(CowStr::from(format!(
+                        "<a class=\"heading-anchor has-text-grey-light ml-2\" href=\"#{}\" aria-hidden=\"true\">#</a>",
+                        id
+                    ))));
+                }
+                result.push(Event::Html(CowStr::from(format!("</h{}>", level))));
+                heading = None
+            }
+            (Some((_, _, inner)), event) => inner.push(event),
+            (None, event) => result.push(event),
+        }
+    }
+    result
+}
+
+pub(crate) struct TocEntry {
+    pub(crate) depth: usize,
+    pub(crate) id: String,
+    pub(crate) title: String,
+}
+
+impl TocEntry {
+    pub(crate) fn indent_class(&self) -> &'static str {
+        match self.depth {
+            0 => "",
+            1 => "pl-4",
+            2 => "pl-5",
+            _ => "pl-6",
+        }
+    }
+}
+
+/// Collects the level, id and text of the headings with an id from rendered html.
+fn toc_entries(html: &str) -> Vec<(usize, String, String)> {
+    let mut result = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find("<h") {
+        rest = &rest[start + 2..];
+        let level = match rest.chars().next().and_then(|c| c.to_digit(10)) {
+            Some(l) if (1..=6).contains(&l) => l as usize,
+            _ => continue,
+        };
+        let open_end = match rest.find('>') {
+            None => break,
+            Some(e) => e,
+        };
+        let attributes = &rest[1..open_end];
+        let id = match attributes
+            .split_once("id=\"")
+            .and_then(|(_, after)| after.split_once('"'))
+        {
+            None => continue,
+            Some((id, _)) => String::from(id),
+        };
+        let closing = format!("</h{}>", level);
+        let inner_end = match rest.find(&closing) {
+            None => break,
+            Some(e) => e,
+        };
+        let inner = &rest[open_end + 1..inner_end];
+        let inner = match inner.find("<a class=\"heading-anchor") {
+            None => inner,
+            Some(a) => &inner[..a],
+        };
+        result.push((level, id, strip_tags(inner)));
+        rest = &rest[inner_end..];
+    }
+    result
+}
+
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    String::from(result.trim())
+}
+
+/// Renders a table of contents for the headings in the html, if there are any.
+pub(crate) fn table_of_contents(html: &str) -> Option<String> {
+    let entries = toc_entries(html);
+    let min_level = entries.iter().map(|(l, _, _)| *l).min()?;
+    let entries: Vec<TocEntry> = entries
+        .into_iter()
+        .map(|(level, id, title)| TocEntry {
+            depth: level - min_level,
+            id,
+            title,
+        })
+        .collect();
+    Some(
+        TableOfContentsTemplate { entries: &entries }
+            .render()
+            .unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Options, Parser};
+
+    fn render(markdown: &str, ids: &mut HeadingIds) -> String {
+        let parser = Parser::new_ext(markdown, Options::ENABLE_HEADING_ATTRIBUTES);
+        let mut html_output = String::new();
+        html::push_html(
+            &mut html_output,
+            add_heading_ids(parser, ids, false).into_iter(),
+        );
+        html_output
+    }
+
+    #[test]
+    fn slugifies_heading_text() {
+        assert_eq!(slugify("Major Scales"), "major-scales");
+        assert_eq!(slugify("  C# & D_flat -- tips "), "c-d-flat-tips");
+        assert_eq!(slugify("Ünïcode Ölé"), "ünïcode-ölé");
+        assert_eq!(slugify("!!!"), "section");
+    }
+
+    #[test]
+    fn numbers_repeated_ids() {
+        let mut ids = HeadingIds::default();
+        assert_eq!(ids.unique(String::from("intro")), "intro");
+        assert_eq!(ids.unique(String::from("intro")), "intro-1");
+        assert_eq!(ids.unique(String::from("intro")), "intro-2");
+        assert_eq!(ids.unique(String::from("outro")), "outro");
+    }
+
+    #[test]
+    fn skips_ids_that_are_already_used() {
+        let mut ids = HeadingIds::default();
+        assert_eq!(ids.unique(String::from("intro-1")), "intro-1");
+        assert_eq!(ids.unique(String::from("intro")), "intro");
+        assert_eq!(ids.unique(String::from("intro")), "intro-2");
+        assert_eq!(ids.unique(String::from("intro-1")), "intro-1-1");
+    }
+
+    #[test]
+    fn shares_ids_between_renders() {
+        let mut ids = HeadingIds::default();
+        let first = render("# Intro\n\n## Intro\n", &mut ids);
+        let second = render("## Intro { #custom }\n\n## Intro\n", &mut ids);
+        assert!(first.contains("<h1 id=\"intro\">Intro</h1>"), "{}", first);
+        assert!(first.contains("<h2 id=\"intro-1\">Intro</h2>"), "{}", first);
+        assert!(
+            second.contains("<h2 id=\"custom\">Intro</h2>"),
+            "{}",
+            second
+        );
+        assert!(
+            second.contains("<h2 id=\"intro-2\">Intro</h2>"),
+            "{}",
+            second
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::config_files::HighlightConfig;
+use crate::files::write_raw;
+use crate::placeholders::Placeholders;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::html::{
+    css_for_theme_with_class_style, line_tokens_to_classed_spans, styled_line_to_highlighted_html,
+    ClassStyle, IncludeBackground,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+const DEFAULT_THEME: &str = "InspiredGitHub";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+const CSS_LINK: &str = "/css/highlight.css";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+impl HighlightConfig {
+    fn get_theme(&self) -> &'static Theme {
+        let themes = &theme_set().themes;
+        match self.theme.as_deref() {
+            None => &themes[DEFAULT_THEME],
+            Some(name) => themes.get(name).unwrap_or_else(|| {
+                println!(
+                    "error: unknown highlight theme {}, using {}",
+                    name, DEFAULT_THEME
+                );
+                &themes[DEFAULT_THEME]
+            }),
+        }
+    }
+    fn inline(&self) -> bool {
+        self.inline_styles.unwrap_or(false)
+    }
+}
+
+/// Writes the css for class based highlighting, returning the link to add to the pages.
+pub(crate) fn write_highlight_css(
+    destination: &str,
+    config: Option<&HighlightConfig>,
+) -> Option<String> {
+    let config = config.filter(|c| !c.inline())?;
+    let theme = config.get_theme();
+    let mut css = css_for_theme_with_class_style(theme, CLASS_STYLE).unwrap();
+    if let Some(bg) = theme.settings.background {
+        writeln!(
+            css,
+            ".content pre.hl-code {{ background-color: {}; }}",
+            hex(bg)
+        )
+        .unwrap();
+    }
+    let line_highlight = line_highlight_color(theme);
+    writeln!(
+        css,
+        ".hl-line {{ display: block; }}\n.hl-line.hl-highlighted {{ background-color: {}; }}\n.hl-line-number {{ display: inline-block; min-width: 2em; margin-right: 1em; text-align: right; opacity: 0.5; user-select: none; }}",
+        line_highlight
+    )
+    .unwrap();
+    write_raw(destination, CSS_LINK, css);
+    Some(String::from(CSS_LINK))
+}
+
+fn hex(c: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+
+fn line_highlight_color(theme: &Theme) -> String {
+    theme
+        .settings
+        .line_highlight
+        .map(hex)
+        .unwrap_or_else(|| String::from("rgba(255, 221, 87, 0.3)"))
+}
+
+/// Options from the info string of a fenced code block, like ```` ```rust linenos hl=2,4-5 ````.
+struct CodeOptions {
+    language: String,
+    line_numbers: bool,
+    highlighted: HashSet<usize>,
+}
+
+impl CodeOptions {
+    fn parse(info: &str, config: &HighlightConfig) -> CodeOptions {
+        let mut parts = info.split_whitespace();
+        let mut options = CodeOptions {
+            language: String::from(parts.next().unwrap_or("")),
+            line_numbers: config.line_numbers.unwrap_or(false),
+            highlighted: HashSet::new(),
+        };
+        for part in parts {
+            match part.split_once('=') {
+                None if part == "linenos" => options.line_numbers = true,
+                None if part == "nolinenos" => options.line_numbers = false,
+                Some(("hl", lines)) => {
+                    for range in lines.split(',') {
+                        let (from, to) = range.split_once('-').unwrap_or((range, range));
+                        match (from.trim().parse::<usize>(), to.trim().parse::<usize>()) {
+                            (Ok(f), Ok(t)) => options.highlighted.extend(f..=t),
+                            _ => println!("error: invalid highlighted lines {}", lines),
+                        }
+                    }
+                }
+                _ => println!("error: unknown code block option {}", part),
+            }
+        }
+        options
+    }
+    fn syntax(&self) -> &'static SyntaxReference {
+        let syntax_set = syntax_set();
+        syntax_set
+            .find_syntax_by_token(&self.language)
+            .unwrap_or_else(|| syntax_set.find_syntax_plain_text())
+    }
+}
+
+/// Replaces the fenced code blocks with markers for the highlighted html, so its inline styles are
+/// kept when sanitizing. Other events are passed on as is.
+pub(crate) fn highlight_code_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    config: &HighlightConfig,
+    generated: &mut Placeholders,
+) -> Vec<Event<'a>> {
+    let mut result = vec![];
+    let mut code_block: Option<(CowStr, String)> = None;
+    for event in events {
+        match (&mut code_block, event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => {
+                code_block = Some((info, String::new()))
+            }
+            (Some((_, code)), Event::Text(text)) => code.push_str(&text),
+            (Some((info, code)), Event::End(Tag::CodeBlock(_))) => {
+                let options = CodeOptions::parse(info, config);
+                let html = highlight(code, &options, config);
+                result.push(Event::Html(CowStr::from(generated.insert(html, false))));
+                code_block = None
+            }
+            (_, event) => result.push(event),
+        }
+    }
+    result
+}
+
+fn highlight(code: &str, options: &CodeOptions, config: &HighlightConfig) -> String {
+    let lines = if config.inline() {
+        inline_lines(code, options, config.get_theme())
+    } else {
+        classed_lines(code, options)
+    };
+    let code_lines: Vec<&str> = LinesWithEndings::from(code).collect();
+    let mut html = String::with_capacity(code.len() * 4);
+    if config.inline() {
+        let theme = config.get_theme();
+        let bg = theme.settings.background.map(hex).unwrap_or_default();
+        let fg = theme.settings.foreground.map(hex).unwrap_or_default();
+        write!(
+            html,
+            "<pre style=\"background-color: {}; color: {};\"><code>",
+            bg, fg
+        )
+        .unwrap();
+    } else {
+        html.push_str("<pre class=\"hl-code\"><code>");
+    }
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        let highlighted = options.highlighted.contains(&number);
+        match (config.inline(), highlighted) {
+            (true, true) => {
+                let color = line_highlight_color(config.get_theme());
+                write!(
+                    html,
+                    "<span style=\"display: block; background-color: {};\">",
+                    color
+                )
+                .unwrap()
+            }
+            (true, false) => html.push_str("<span style=\"display: block;\">"),
+            (false, true) => html.push_str("<span class=\"hl-line hl-highlighted\">"),
+            (false, false) => html.push_str("<span class=\"hl-line\">"),
+        }
+        if options.line_numbers {
+            if config.inline() {
+                write!(html, "<span style=\"display: inline-block; min-width: 2em; margin-right: 1em; text-align: right; opacity: 0.5; user-select: none;\">{}</span>", number).unwrap()
+            } else {
+                write!(html, "<span class=\"hl-line-number\">{}</span>", number).unwrap()
+            }
+        }
+        // the lines are blocks, so the newlines are dropped, and empty lines need some content
+        html.push_str(&line.replace(['\n', '\r'], ""));
+        if code_lines.get(i).is_none_or(|l| l.trim().is_empty()) {
+            html.push(' ');
+        }
+        html.push_str("</span>");
+    }
+    html.push_str("</code></pre>");
+    html
+}
+
+fn inline_lines(code: &str, options: &CodeOptions, theme: &Theme) -> Vec<String> {
+    let mut highlighter = HighlightLines::new(options.syntax(), theme);
+    let mut result = vec![];
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, syntax_set()).unwrap();
+        result.push(styled_line_to_highlighted_html(&regions, IncludeBackground::No).unwrap());
+    }
+    result
+}
+
+/// Every line is wrapped on its own, so spans that are still open at the end of a line are closed,
+/// and opened again at the start of the next one.
+fn classed_lines(code: &str, options: &CodeOptions) -> Vec<String> {
+    let mut parse_state = ParseState::new(options.syntax());
+    let mut stack = ScopeStack::new();
+    let mut result = vec![];
+    for line in LinesWithEndings::from(code) {
+        let mut html = String::new();
+        for scope in stack.as_slice() {
+            html.push_str("<span class=\"");
+            let classes: Vec<String> = scope
+                .build_string()
+                .split('.')
+                .map(|atom| format!("hl-{}", atom))
+                .collect();
+            html.push_str(&classes.join(" "));
+            html.push_str("\">");
+        }
+        let open = stack.len() as isize;
+        let ops = parse_state.parse_line(line, syntax_set()).unwrap();
+        let (spans, delta) =
+            line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut stack).unwrap();
+        html.push_str(&spans);
+        for _ in 0..(open + delta) {
+            html.push_str("</span>");
+        }
+        result.push(html);
+    }
+    result
+}
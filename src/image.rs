@@ -1,6 +1,6 @@
 use crate::bulma::ImageRatio;
 use crate::config_files::ImageReference;
-use crate::files::{empty, write_raw};
+use crate::files::{empty, record_written, write_raw};
 use dashmap::DashMap;
 use fast_image_resize::{FilterType, Image, MulDiv, PixelType, ResizeAlg, Resizer};
 use image::io::Reader;
@@ -112,6 +112,7 @@ impl<'a> ImageProcessor<'a> {
             Some((first, _)) => String::from(first),
         };
         let mut srcset_part = vec![];
+        let mut src = String::new();
         for (width, quality) in all_widths(width) {
             let path = format!("/img{}/{}-w{}.webp", path_start, &file_base, &width);
             if empty(self.destination, &path) {
@@ -138,10 +139,12 @@ impl<'a> ImageProcessor<'a> {
                 let enc = Encoder::from_rgba(dst_image.buffer(), dst_width.get(), dst_height.get());
                 let mut result = enc.encode(quality);
                 write_raw(self.destination, &path, result.iter_mut());
+            } else {
+                record_written(&path);
             }
             srcset_part.push(format!("{} {}w", &path, width));
+            src = path;
         }
-        let src = srcset_part.last().unwrap().clone();
         let srcset = srcset_part.join(", ");
         let base_path = if path_start.is_empty() {
             format!("/{}", &file_base)
@@ -1,8 +1,29 @@
+mod admonitions;
+mod authors;
 mod bulma;
+mod collections;
 mod config_files;
 mod content;
+mod data;
+mod dates;
+mod favicon;
 mod files;
 pub mod generator;
+mod headings;
+mod highlight;
 mod image;
+mod links;
+mod listings;
+mod markdown;
+mod math;
+mod placeholders;
+mod related;
+mod sanitize;
+mod shortcodes;
+mod siblings;
+mod stats;
 mod structure;
+mod tables;
+mod taxonomies;
 mod templates;
+mod verify;
@@ -0,0 +1,196 @@
+use crate::content::ContentContext;
+use crate::headings::strip_tags;
+use crate::structure::Structure;
+
+/// Where a local link points to, after resolving it against the page it is on.
+enum Target {
+    Page(String),
+    Asset(String),
+    Broken,
+}
+
+fn is_external(url: &str) -> bool {
+    url.is_empty()
+        || url.starts_with('#')
+        || url.starts_with("//")
+        || url
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains('/'))
+}
+
+/// Resolves `.` and `..` segments, relative urls are taken relative to the page, which is a
+/// directory in the source.
+fn normalize(url: &str, page_path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    let joined = if url.starts_with('/') {
+        String::from(url)
+    } else {
+        format!("{}/{}", page_path, url)
+    };
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Resolves a link to a page, the output url of a page like `/lessons/scales.html`, or a source file
+/// of a page like `/lessons/scales/content.md`. Other links with an extension are assets.
+fn resolve(path: &str, structure: &Structure) -> Target {
+    if structure.has_item(path) {
+        return Target::Page(String::from(path));
+    }
+    // checked first, as a .html link is only a source file when it isn't the output of a page
+    if let Some(stripped) = path.strip_suffix(".html") {
+        if structure.has_item(stripped) {
+            return Target::Page(String::from(stripped));
+        }
+    }
+    let (parent, last) = path.rsplit_once('/').unwrap_or(("", path));
+    let parent = if parent.is_empty() { "/" } else { parent };
+    let is_source_file = last == "page.json" || last.ends_with(".md") || last.ends_with(".html");
+    if is_source_file && structure.has_item(parent) {
+        return Target::Page(String::from(parent));
+    }
+    if last.contains('.') {
+        Target::Asset(String::from(path))
+    } else {
+        Target::Broken
+    }
+}
+
+/// Checks a single link, returning the url it should be replaced with.
+fn check_link(url: &str, text: &str, context: &ContentContext) -> String {
+    if is_external(url) {
+        return String::from(url);
+    }
+    let split = url.find(['#', '?']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(split);
+    match resolve(&normalize(path, context.path), context.structure) {
+        Target::Page(p) => {
+            context.structure.add_link(context.path, &p);
+            let output = String::from(context.structure.get_item(&p).output_path());
+            format!("{}{}", output, suffix)
+        }
+        Target::Asset(a) => format!("{}{}", a, suffix),
+        Target::Broken => {
+            println!(
+                "error: Broken link {} ({}) on page {}",
+                url, text, context.path
+            );
+            String::from(url)
+        }
+    }
+}
+
+/// Checks the links in rendered content, links to pages, their source files and relative links
+/// are rewritten to the output url of the page. Broken links are reported with their text.
+pub(crate) fn check_links(html: &str, context: &ContentContext) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("<a ") {
+        let tag_end = match rest[start..].find('>') {
+            None => break,
+            Some(e) => start + e,
+        };
+        let tag = &rest[start..tag_end];
+        let href = tag
+            .find("href=\"")
+            .map(|h| h + 6)
+            .and_then(|h| tag[h..].find('"').map(|e| (h, h + e)));
+        match href {
+            None => result.push_str(&rest[..tag_end]),
+            Some((from, to)) => {
+                let after = &rest[tag_end + 1..];
+                let text = strip_tags(&after[..after.find("</a>").unwrap_or(0)]);
+                result.push_str(&rest[..start + from]);
+                result.push_str(&check_link(&tag[from..to], &text, context));
+                result.push_str(&rest[start + to..tag_end]);
+            }
+        }
+        rest = &rest[tag_end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_files::PageConfig;
+    use crate::data::SiteData;
+    use crate::structure::Item;
+    use dashmap::DashMap;
+    use std::collections::BTreeMap;
+
+    fn structure(paths: &[&str]) -> Structure {
+        let structure = Structure::new(DashMap::new(), SiteData::default(), BTreeMap::new());
+        for path in paths {
+            structure.add_item(Item::new(String::from(*path), PageConfig::new(path)));
+        }
+        structure
+    }
+
+    fn page(path: &str, structure: &Structure) -> Option<String> {
+        match resolve(path, structure) {
+            Target::Page(p) => Some(p),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn resolves_pages() {
+        let structure = structure(&["/", "/lessons", "/lessons/scales"]);
+        assert_eq!(
+            page("/lessons/scales", &structure).unwrap(),
+            "/lessons/scales"
+        );
+        assert_eq!(page("/", &structure).unwrap(), "/");
+    }
+
+    #[test]
+    fn resolves_the_output_url_of_a_page() {
+        let structure = structure(&["/", "/lessons", "/lessons/scales"]);
+        assert_eq!(
+            page("/lessons/scales.html", &structure).unwrap(),
+            "/lessons/scales"
+        );
+        assert_eq!(page("/lessons.html", &structure).unwrap(), "/lessons");
+    }
+
+    #[test]
+    fn resolves_source_files_to_their_page() {
+        let structure = structure(&["/", "/lessons", "/lessons/scales"]);
+        for file in ["page.json", "content.md", "intro.html"] {
+            let path = format!("/lessons/scales/{}", file);
+            assert_eq!(page(&path, &structure).unwrap(), "/lessons/scales");
+        }
+        assert_eq!(page("/content.md", &structure).unwrap(), "/");
+    }
+
+    #[test]
+    fn leaves_assets_and_reports_broken_links() {
+        let structure = structure(&["/", "/lessons"]);
+        assert!(matches!(
+            resolve("/files/tab.pdf", &structure),
+            Target::Asset(a) if a == "/files/tab.pdf"
+        ));
+        assert!(matches!(
+            resolve("/lessons/chords", &structure),
+            Target::Broken
+        ));
+    }
+
+    #[test]
+    fn normalizes_relative_links() {
+        assert_eq!(normalize("scales", "/lessons"), "/lessons/scales");
+        assert_eq!(normalize("../about", "/lessons/scales"), "/lessons/about");
+        assert_eq!(normalize("./content.md", "/"), "/content.md");
+        assert_eq!(normalize("/a/./b/../c", "/x"), "/a/c");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::bulma::ImageType;
+use crate::config_files::{ListingStyle, PageConfig, SpecialPage};
+use crate::structure::{sort_items, Item, Structure};
+use crate::templates::{Card, CardsTemplate, ListingTemplate, PageLink};
+use askama::Template;
+use std::sync::Arc;
+
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+fn page_url(section: &str, number: usize) -> String {
+    match (section, number) {
+        (s, 1) => String::from(s),
+        ("/", n) => format!("/page/{}", n),
+        (s, n) => format!("{}/page/{}", s, n),
+    }
+}
+
+fn page_count(children: usize, page_size: usize) -> usize {
+    children.div_ceil(page_size.max(1)).max(1)
+}
+
+/// Creates the items for the second and later pages of the sections with a listing, the first page
+/// is the section itself.
+pub(crate) fn listing_items(structure: &Structure) -> Vec<Item> {
+    let mut result = vec![];
+    for section in structure.get_listed_sections() {
+        let listing = section.listing.as_ref().unwrap();
+        let children = structure.get_children(&section.path).len();
+        let pages = page_count(children, listing.page_size.unwrap_or(DEFAULT_PAGE_SIZE));
+        for number in 2..=pages {
+            let path = page_url(&section.path, number);
+            if structure.has_item(&path) {
+                println!(
+                    "error: Page {} is also used for page {} of the listing of {}",
+                    path, number, section.path
+                );
+            }
+            let mut page_config = PageConfig::new(&path);
+            page_config.title = format!("{}, page {}", section.title, number);
+            page_config.sub_title = section.sub_title.clone();
+            page_config.content = String::new();
+            page_config.special = Some(SpecialPage::Unlisted);
+            let mut item = Item::new(path, page_config);
+            item.listing_page = Some((section.path.clone(), number));
+            result.push(item);
+        }
+    }
+    result
+}
+
+fn to_card(item: &Item, structure: &Structure, with_image: bool) -> Card {
+    Card {
+        title: item.title.clone(),
+        subtitle: item.sub_title.clone(),
+        content: structure.get_summary(item),
+        image: match with_image {
+            true => item
+                .image
+                .as_ref()
+                .and_then(|i| structure.process_image(i, ImageType::Side)),
+            false => None,
+        },
+        url: Some(item.path.clone()),
+    }
+}
+
+/// Renders the part of the listing of the section shown on the page, with the controls to go to
+/// the other pages. This needs the summaries, so it's done once all main content is rendered.
+pub(crate) fn add_listing(path: &str, structure: &Structure) {
+    let item = structure.get_item(path);
+    let (section, number) = match (&item.listing, &item.listing_page) {
+        (Some(_), _) => (item.clone(), 1),
+        (None, Some((section, number))) => (structure.get_item(section), *number),
+        (None, None) => return,
+    };
+    let listing = section.listing.as_ref().unwrap();
+    let mut children: Vec<Arc<Item>> = structure.get_children(&section.path);
+    if let Some(strategy) = &listing.sort {
+        sort_items(&mut children, Some(strategy))
+    }
+    if listing.descending == Some(true) {
+        children.reverse()
+    }
+    let page_size = listing.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let cards = listing.style == Some(ListingStyle::Cards);
+    let entries: Vec<Card> = children
+        .iter()
+        .skip((number - 1) * page_size)
+        .take(page_size)
+        .map(|c| to_card(c, structure, cards))
+        .collect();
+    let pages: Vec<PageLink> = (1..=page_count(children.len(), page_size))
+        .map(|n| PageLink {
+            number: n,
+            url: page_url(&section.path, n),
+            current: n == number,
+        })
+        .collect();
+    let html = ListingTemplate {
+        cards: cards.then(|| CardsTemplate { cards: &entries }.render().unwrap()),
+        entries: &entries,
+        previous: (number > 1).then(|| page_url(&section.path, number - 1)),
+        next: (number < pages.len()).then(|| page_url(&section.path, number + 1)),
+        pages: &pages,
+    }
+    .render()
+    .unwrap();
+    structure.add_listing(path, html)
+}
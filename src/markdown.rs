@@ -0,0 +1,138 @@
+use crate::admonitions::extract_admonitions;
+use crate::bulma::ImageType;
+use crate::content::{to_internal_image, ContentContext};
+use crate::headings::add_heading_ids;
+use crate::highlight::highlight_code_blocks;
+use crate::math::extract_math;
+use crate::placeholders::Placeholders;
+use pulldown_cmark::{html, CowStr, Event, Parser, Tag};
+
+/// Prefix for markdown images that refer to a key of the image cache instead of a url.
+const IMAGE_PREFIX: &str = "img:";
+
+/// The length of the code the markdown starts with, a fenced code block when at the start of a line,
+/// or a code span. Backticks without a closing sequence are returned as is. Used by the passes over
+/// the source that should leave code alone.
+pub(crate) fn code_length(text: &str, at_line_start: bool) -> Option<usize> {
+    let trimmed = text.trim_start_matches([' ', '\t']);
+    if at_line_start && (trimmed.starts_with("```") || trimmed.starts_with("~~~")) {
+        let fence = &trimmed[..3];
+        let mut end = text.find('\n').map(|e| e + 1).unwrap_or(text.len());
+        while end < text.len() {
+            let line_end = text[end..]
+                .find('\n')
+                .map(|e| end + e + 1)
+                .unwrap_or(text.len());
+            let closing = text[end..line_end]
+                .trim_start_matches([' ', '\t'])
+                .starts_with(fence);
+            end = line_end;
+            if closing {
+                break;
+            }
+        }
+        return Some(end);
+    }
+    if !text.starts_with('`') {
+        return None;
+    }
+    let ticks = text.len() - text.trim_start_matches('`').len();
+    let fence = &text[..ticks];
+    Some(
+        text[ticks..]
+            .find(fence)
+            .map(|e| ticks + e + ticks)
+            .unwrap_or(ticks),
+    )
+}
+
+pub(crate) fn markdown_to_html(text: &str, context: &ContentContext) -> String {
+    if context.math {
+        let extracted = extract_math(text, context.path);
+        return extracted.restore(&render_admonitions(&extracted.text, context));
+    }
+    render_admonitions(text, context)
+}
+
+fn render_admonitions(text: &str, context: &ContentContext) -> String {
+    let extracted = extract_admonitions(text, context);
+    extracted.restore(&render(&extracted.text, context))
+}
+
+/// Renders the markdown, the markup that is generated with inline styles or inputs is replaced by
+/// markers until the html of the author is sanitized.
+fn render(text: &str, context: &ContentContext) -> String {
+    let mut html_output: String = String::with_capacity(text.len() * 3 / 2);
+    let mut generated = Placeholders::new("VADOSGENERATED", 0);
+    let parser = Parser::new_ext(text, context.markdown_options).map(|event| match event {
+        Event::TaskListMarker(checked) => {
+            let checked = if checked { " checked=\"\"" } else { "" };
+            let html = format!("<input disabled=\"\" type=\"checkbox\"{}/>\n", checked);
+            Event::Html(CowStr::from(generated.insert(html, false)))
+        }
+        event => event,
+    });
+    let events = replace_image_references(parser, context);
+    let events = add_heading_ids(
+        events.into_iter(),
+        &mut context.heading_ids.borrow_mut(),
+        context.heading_anchors,
+    );
+    match context.highlighting {
+        None => html::push_html(&mut html_output, events.into_iter()),
+        Some(config) => html::push_html(
+            &mut html_output,
+            highlight_code_blocks(events.into_iter(), config, &mut generated).into_iter(),
+        ),
+    }
+    generated
+        .restore(&context.sanitize(&html_output))
+        .replace("<table>", "<table class=\"table is-striped\">")
+}
+
+/// Replaces images like `![alt](img:/lessons/scale)` with the responsive processed image. When the
+/// image is the only thing in a paragraph, the paragraph is dropped, as a figure can't be in one.
+fn replace_image_references<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    context: &ContentContext,
+) -> Vec<Event<'a>> {
+    let mut result = vec![];
+    let mut image: Option<(String, String)> = None;
+    let mut replaced_at = None;
+    for event in events {
+        match (&mut image, event) {
+            (None, Event::Start(Tag::Image(_, url, _))) if url.starts_with(IMAGE_PREFIX) => {
+                image = Some((String::from(&url[IMAGE_PREFIX.len()..]), String::new()))
+            }
+            (Some((_, alt)), Event::Text(text)) => alt.push_str(&text),
+            (Some((key, alt)), Event::End(Tag::Image(..))) => {
+                match context.structure.get_processed_image(key) {
+                    Some(processed) => {
+                        let alt = Some(alt.as_str()).filter(|a| !a.is_empty());
+                        let html = to_internal_image(processed, ImageType::Content, alt);
+                        result.push(Event::Html(CowStr::from(html)));
+                        replaced_at = Some(result.len());
+                    }
+                    None => {
+                        println!(
+                            "error: No image was found with reference {} on page {}",
+                            key, context.path
+                        );
+                        result.push(Event::Text(CowStr::from(alt.clone())));
+                    }
+                }
+                image = None
+            }
+            (Some(_), _) => {}
+            (None, Event::End(Tag::Paragraph))
+                if replaced_at == Some(result.len())
+                    && result.len() >= 2
+                    && result[result.len() - 2] == Event::Start(Tag::Paragraph) =>
+            {
+                result.remove(result.len() - 2);
+            }
+            (None, event) => result.push(event),
+        }
+    }
+    result
+}
@@ -0,0 +1,406 @@
+use crate::markdown::code_length;
+use crate::placeholders::Placeholders;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug)]
+struct MathError {
+    details: String,
+}
+
+impl Display for MathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for MathError {}
+
+fn error<T>(details: String) -> Result<T, MathError> {
+    Err(MathError { details })
+}
+
+/// Replaces `$$...$$` and `$...$` outside of code with markers for the rendered MathML. Inline math
+/// should not start or end with whitespace, and the closing `$` not be followed by a digit, so
+/// amounts like $5 and $10 are left alone. Bad expressions are reported and shown as code.
+pub(crate) fn extract_math(text: &str, origin: &str) -> Placeholders {
+    let mut extracted = Placeholders::new("VADOSMATH", text.len());
+    if text.contains('$') {
+        replace_math(text, &mut extracted, origin)
+    } else {
+        extracted.text.push_str(text)
+    }
+    extracted
+}
+
+fn replace_math(text: &str, extracted: &mut Placeholders, origin: &str) {
+    let mut at_line_start = true;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(length) = code_length(rest, at_line_start) {
+            extracted.text.push_str(&rest[..length]);
+            at_line_start = rest[..length].ends_with('\n');
+            rest = &rest[length..];
+            continue;
+        }
+        at_line_start = c == '\n';
+        match c {
+            '\\' if rest[1..].starts_with('$') => {
+                extracted.text.push_str("\\$");
+                rest = &rest[2..];
+            }
+            '$' => match find_math(rest) {
+                None => {
+                    extracted.text.push('$');
+                    rest = &rest[1..];
+                }
+                Some((display, source, consumed)) => {
+                    extracted.push(render_math(source, display, origin), display);
+                    rest = &rest[consumed..];
+                }
+            },
+            c => {
+                extracted.text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+}
+
+/// Finds the math starting at a `$`, returning whether it's display math, the expression and the
+/// number of bytes consumed. Inline math doesn't continue into a code span or the next paragraph.
+fn find_math(text: &str) -> Option<(bool, &str, usize)> {
+    if let Some(after) = text.strip_prefix("$$") {
+        let end = after.find("$$")?;
+        return Some((true, after[..end].trim(), end + 4));
+    }
+    let after = &text[1..];
+    if after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut previous = '$';
+    for (i, c) in after.char_indices() {
+        match c {
+            '`' => return None,
+            '\n' if previous == '\n' => return None,
+            '$' if previous != '\\' => {
+                let next = after[i + 1..].chars().next();
+                if previous.is_whitespace() || next.is_some_and(|n| n.is_ascii_digit()) {
+                    return None;
+                }
+                return Some((false, &after[..i], i + 2));
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+fn render_math(source: &str, display: bool, origin: &str) -> String {
+    let delimiter = if display { "$$" } else { "$" };
+    match to_mathml(source) {
+        Ok(mathml) => {
+            let display = if display { " display=\"block\"" } else { "" };
+            format!(
+                "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"{}><mrow>{}</mrow></math>",
+                display, mathml
+            )
+        }
+        Err(e) => {
+            println!(
+                "error: {} in math expression {}{}{} in {}",
+                e, delimiter, source, delimiter, origin
+            );
+            format!(
+                "<code class=\"has-text-danger\">{}{}{}</code>",
+                delimiter,
+                escape(source),
+                delimiter
+            )
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Converts a subset of TeX, enough for fractions, roots, scripts, greek letters and the common
+/// operators, to MathML.
+fn to_mathml(source: &str) -> Result<String, MathError> {
+    let mut parser = MathParser {
+        chars: source.chars().peekable(),
+    };
+    let result = parser.expression()?;
+    match parser.chars.next() {
+        None => Ok(result),
+        Some(c) => error(format!("Unexpected {}", c)),
+    }
+}
+
+struct MathParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl MathParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+    /// Parses atoms with their scripts until the end of the input or a closing brace.
+    fn expression(&mut self) -> Result<String, MathError> {
+        let mut result = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                None | Some('}') => return Ok(result),
+                Some('^') | Some('_') => {
+                    return error(String::from("Script without something to attach it to"))
+                }
+                Some(_) => {
+                    let atom = self.atom()?;
+                    result.push_str(&self.scripts(atom)?);
+                }
+            }
+        }
+    }
+    fn scripts(&mut self, base: String) -> Result<String, MathError> {
+        let mut sub = None;
+        let mut sup = None;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('_') if sub.is_none() => {
+                    self.chars.next();
+                    sub = Some(self.argument()?);
+                }
+                Some('^') if sup.is_none() => {
+                    self.chars.next();
+                    sup = Some(self.argument()?);
+                }
+                Some('_') | Some('^') => return error(String::from("Double script")),
+                _ => break,
+            }
+        }
+        Ok(match (sub, sup) {
+            (None, None) => base,
+            (Some(b), None) => format!("<msub>{}{}</msub>", base, b),
+            (None, Some(p)) => format!("<msup>{}{}</msup>", base, p),
+            (Some(b), Some(p)) => format!("<msubsup>{}{}{}</msubsup>", base, b, p),
+        })
+    }
+    /// A group in braces, or a single atom, as used for the arguments of commands and scripts.
+    fn argument(&mut self) -> Result<String, MathError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None | Some('}') => error(String::from("Missing argument")),
+            Some('{') => {
+                self.chars.next();
+                let inner = self.expression()?;
+                self.close_group()?;
+                Ok(format!("<mrow>{}</mrow>", inner))
+            }
+            Some(_) => self.atom(),
+        }
+    }
+    fn close_group(&mut self) -> Result<(), MathError> {
+        match self.chars.next() {
+            Some('}') => Ok(()),
+            _ => error(String::from("Missing closing }")),
+        }
+    }
+    fn text_argument(&mut self) -> Result<String, MathError> {
+        self.skip_whitespace();
+        if self.chars.next() != Some('{') {
+            return error(String::from("Expected { for text"));
+        }
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                None => return error(String::from("Missing closing }")),
+                Some('}') => return Ok(text),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+    fn atom(&mut self) -> Result<String, MathError> {
+        let c = match self.chars.next() {
+            None => return error(String::from("Unexpected end")),
+            Some(c) => c,
+        };
+        match c {
+            '{' => {
+                let inner = self.expression()?;
+                self.close_group()?;
+                Ok(format!("<mrow>{}</mrow>", inner))
+            }
+            '\\' => self.command(),
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::from(c);
+                while let Some(d) = self.chars.next_if(|d| d.is_ascii_digit() || *d == '.') {
+                    number.push(d)
+                }
+                Ok(format!("<mn>{}</mn>", number))
+            }
+            c if c.is_alphabetic() => Ok(format!("<mi>{}</mi>", c)),
+            '+' | '-' | '=' | '<' | '>' | '(' | ')' | '[' | ']' | '/' | '|' | ',' | ';' | ':'
+            | '!' | '*' | '\'' => {
+                let c = match c {
+                    '-' => String::from("−"),
+                    '*' => String::from("∗"),
+                    '\'' => String::from("′"),
+                    c => escape(&String::from(c)),
+                };
+                Ok(format!("<mo>{}</mo>", c))
+            }
+            c => error(format!("Unexpected {}", c)),
+        }
+    }
+    fn command(&mut self) -> Result<String, MathError> {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+            name.push(c)
+        }
+        if name.is_empty() {
+            return match self.chars.next() {
+                Some(',') => Ok(String::from("<mspace width=\"0.167em\"></mspace>")),
+                Some(';') => Ok(String::from("<mspace width=\"0.278em\"></mspace>")),
+                Some(' ') => Ok(String::from("<mspace width=\"0.25em\"></mspace>")),
+                Some(c @ ('{' | '}' | '$' | '%' | '#' | '&' | '_')) => {
+                    Ok(format!("<mo>{}</mo>", escape(&String::from(c))))
+                }
+                Some(c) => error(format!("Unknown command \\{}", c)),
+                None => error(String::from("Unexpected end after \\")),
+            };
+        }
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument()?;
+                let denominator = self.argument()?;
+                Ok(format!("<mfrac>{}{}</mfrac>", numerator, denominator))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&'[').is_some() {
+                    let mut index = String::new();
+                    loop {
+                        match self.chars.next() {
+                            None => return error(String::from("Missing closing ]")),
+                            Some(']') => break,
+                            Some(c) => index.push(c),
+                        }
+                    }
+                    let radicand = self.argument()?;
+                    Ok(format!(
+                        "<mroot>{}<mrow>{}</mrow></mroot>",
+                        radicand,
+                        to_mathml(&index)?
+                    ))
+                } else {
+                    Ok(format!("<msqrt>{}</msqrt>", self.argument()?))
+                }
+            }
+            "text" | "mathrm" => Ok(format!("<mtext>{}</mtext>", escape(&self.text_argument()?))),
+            "left" | "right" => {
+                self.skip_whitespace();
+                match self.chars.next() {
+                    Some('.') => Ok(String::new()),
+                    Some('\\') => self.command(),
+                    Some(c) => Ok(format!(
+                        "<mo stretchy=\"true\">{}</mo>",
+                        escape(&String::from(c))
+                    )),
+                    None => error(format!("Missing delimiter after \\{}", name)),
+                }
+            }
+            "quad" => Ok(String::from("<mspace width=\"1em\"></mspace>")),
+            "qquad" => Ok(String::from("<mspace width=\"2em\"></mspace>")),
+            "sin" | "cos" | "tan" | "log" | "ln" | "exp" | "lim" | "min" | "max" => {
+                Ok(format!("<mi>{}</mi>", name))
+            }
+            other => match symbol(other) {
+                Some((true, s)) => Ok(format!("<mo>{}</mo>", s)),
+                Some((false, s)) => Ok(format!("<mi>{}</mi>", s)),
+                None => error(format!("Unknown command \\{}", other)),
+            },
+        }
+    }
+}
+
+/// The character for a symbol command, and whether it's an operator.
+fn symbol(name: &str) -> Option<(bool, char)> {
+    let identifier = match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        "infty" => '∞',
+        "partial" => '∂',
+        _ => ' ',
+    };
+    if identifier != ' ' {
+        return Some((false, identifier));
+    }
+    let operator = match name {
+        "times" => '×',
+        "cdot" => '⋅',
+        "div" => '÷',
+        "pm" => '±',
+        "mp" => '∓',
+        "leq" | "le" => '≤',
+        "geq" | "ge" => '≥',
+        "neq" | "ne" => '≠',
+        "approx" => '≈',
+        "equiv" => '≡',
+        "sim" => '∼',
+        "propto" => '∝',
+        "to" | "rightarrow" => '→',
+        "leftarrow" => '←',
+        "Rightarrow" => '⇒',
+        "cdots" => '⋯',
+        "ldots" | "dots" => '…',
+        "sum" => '∑',
+        "prod" => '∏',
+        "int" => '∫',
+        "in" => '∈',
+        "sharp" => '♯',
+        "flat" => '♭',
+        "natural" => '♮',
+        _ => return None,
+    };
+    Some((true, operator))
+}
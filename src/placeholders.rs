@@ -0,0 +1,44 @@
+/// Text with parts replaced by markers, which survive markdown rendering, and are replaced by the
+/// rendered html with `restore`. Used for shortcodes, math and admonitions, each with its own prefix
+/// so they can be nested.
+pub(crate) struct Placeholders {
+    pub(crate) text: String,
+    prefix: &'static str,
+    /// The html for each marker, and whether it's a block that replaces the paragraph markdown puts
+    /// around a marker on its own.
+    replacements: Vec<(bool, String)>,
+}
+
+impl Placeholders {
+    pub(crate) fn new(prefix: &'static str, capacity: usize) -> Placeholders {
+        Placeholders {
+            text: String::with_capacity(capacity),
+            prefix,
+            replacements: vec![],
+        }
+    }
+    /// Adds a marker for the html to the end of the text.
+    pub(crate) fn push(&mut self, html: String, block: bool) {
+        let marker = self.insert(html, block);
+        self.text.push_str(&marker);
+    }
+    /// Stores the html, returning the marker for it, for when the text is built elsewhere.
+    pub(crate) fn insert(&mut self, html: String, block: bool) -> String {
+        self.replacements.push((block, html));
+        self.marker(self.replacements.len() - 1)
+    }
+    pub(crate) fn restore(&self, html: &str) -> String {
+        let mut result = String::from(html);
+        for (i, (block, replacement)) in self.replacements.iter().enumerate() {
+            let marker = self.marker(i);
+            if *block {
+                result = result.replace(&format!("<p>{}</p>", marker), replacement);
+            }
+            result = result.replace(&marker, replacement);
+        }
+        result
+    }
+    fn marker(&self, i: usize) -> String {
+        format!("{}{}X", self.prefix, i)
+    }
+}
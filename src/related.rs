@@ -0,0 +1,46 @@
+use crate::structure::{Item, Structure};
+use std::sync::Arc;
+
+const SHARED_TERM_SCORE: usize = 2;
+const LINK_SCORE: usize = 3;
+const SAME_SECTION_SCORE: usize = 1;
+
+fn shared(a: &[String], b: &[String]) -> usize {
+    a.iter().filter(|t| b.contains(t)).count()
+}
+
+/// How related the other page is, for each tag or category they share, for linking to each other,
+/// and for having the same parent.
+fn score(item: &Item, other: &Item, structure: &Structure) -> usize {
+    let mut score = SHARED_TERM_SCORE
+        * (shared(&item.tags, &other.tags) + shared(&item.categories, &other.categories));
+    if structure.links_to(&item.path, &other.path) {
+        score += LINK_SCORE
+    }
+    if structure.links_to(&other.path, &item.path) {
+        score += LINK_SCORE
+    }
+    if is_sibling(item, other) {
+        score += SAME_SECTION_SCORE
+    }
+    score
+}
+
+fn is_sibling(item: &Item, other: &Item) -> bool {
+    let parent = |p: &str| p.rsplit_once('/').map(|(first, _)| String::from(first));
+    parent(&item.path) == parent(&other.path)
+}
+
+/// The listed pages most related to the item, best first, pages that are equally related are
+/// sorted like the pages of a directory.
+pub(crate) fn related_items(item: &Item, structure: &Structure, count: usize) -> Vec<Arc<Item>> {
+    let mut scored: Vec<(usize, Arc<Item>)> = structure
+        .get_listed_items()
+        .into_iter()
+        .filter(|other| other.path != item.path)
+        .map(|other| (score(item, &other, structure), other))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then(a.cmp(b)));
+    scored.into_iter().take(count).map(|(_, i)| i).collect()
+}
@@ -0,0 +1,116 @@
+use crate::config_files::SanitizeConfig;
+use ammonia::Builder;
+use std::collections::BTreeMap;
+
+const TAGS: [&str; 4] = ["article", "figure", "picture", "source"];
+
+const GENERIC_ATTRIBUTES: [&str; 5] = ["aria-hidden", "class", "id", "role", "title"];
+
+const TAG_ATTRIBUTES: [(&str, &[&str]); 3] = [
+    ("a", &["target"]),
+    ("img", &["decoding", "loading", "sizes", "srcset"]),
+    ("source", &["media", "sizes", "srcset", "type"]),
+];
+
+/// The alignment of table columns is the only style that is kept.
+const ALIGNMENTS: [&str; 3] = [
+    "text-align: left",
+    "text-align: center",
+    "text-align: right",
+];
+
+/// Cleans the html the author wrote against an allowlist of tags and attributes: the defaults of
+/// ammonia, the responsive images and table alignment markdown renders to, and the tags and
+/// attributes from the config. It runs before shortcodes, math, admonitions and highlighting add
+/// their markup, so these don't have to be allowed.
+pub(crate) struct Sanitizer<'a> {
+    builder: Builder<'a>,
+}
+
+impl<'a> Sanitizer<'a> {
+    pub(crate) fn new(config: Option<&'a SanitizeConfig>) -> Sanitizer<'a> {
+        let mut builder = Builder::default();
+        builder
+            .add_tags(TAGS)
+            .add_generic_attributes(GENERIC_ATTRIBUTES);
+        for (tag, attributes) in TAG_ATTRIBUTES {
+            builder.add_tag_attributes(tag, attributes.iter().copied());
+        }
+        for tag in ["td", "th"] {
+            builder.add_tag_attribute_values(tag, "style", &ALIGNMENTS);
+        }
+        if let Some(tags) = config.and_then(|c| c.tags.as_ref()) {
+            builder.add_tags(tags.iter().map(|t| t.as_str()));
+        }
+        if let Some(attributes) = config.and_then(|c| c.attributes.as_ref()) {
+            builder.add_generic_attributes(attributes.iter().map(|a| a.as_str()));
+        }
+        Sanitizer { builder }
+    }
+    /// Cleans the html, reporting the tags and attributes that were removed.
+    pub(crate) fn clean(&self, html: &str, path: &str) -> String {
+        let cleaned = self.builder.clean(html).to_string();
+        let before = count_markup(html);
+        let after = count_markup(&cleaned);
+        let stripped: Vec<String> = before
+            .into_iter()
+            .filter_map(|(markup, count)| {
+                let removed = count.saturating_sub(after.get(&markup).copied().unwrap_or(0));
+                (removed > 0).then(|| format!("{} ({}x)", markup, removed))
+            })
+            .collect();
+        if !stripped.is_empty() {
+            println!(
+                "error: Sanitizing content on page {} stripped {}",
+                path,
+                stripped.join(", ")
+            );
+        }
+        cleaned
+    }
+}
+
+/// Counts the opening tags, and the attributes per tag, in html.
+fn count_markup(html: &str) -> BTreeMap<String, usize> {
+    let mut result = BTreeMap::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        *result.entry(format!("<{}>", name)).or_insert(0) += 1;
+        rest = &rest[name_end..];
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let attribute_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '>')
+                .unwrap_or(rest.len());
+            let key = format!("{} on <{}>", rest[..attribute_end].to_lowercase(), name);
+            *result.entry(key).or_insert(0) += 1;
+            rest = &rest[attribute_end..];
+            if let Some(value) = rest.strip_prefix('=') {
+                rest = match value.chars().next() {
+                    Some(q @ ('"' | '\'')) => match value[1..].find(q) {
+                        None => "",
+                        Some(e) => &value[e + 2..],
+                    },
+                    _ => {
+                        let e = value
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        &value[e..]
+                    }
+                };
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,526 @@
+use crate::bulma::{Color, ImageType};
+use crate::config_files::{ContentSettings, MainConfig, TableConfig};
+use crate::content::{get_file_path, ContentContext};
+use crate::files::PARTIALS_DIRECTORY;
+use crate::markdown::{code_length, markdown_to_html};
+use crate::placeholders::Placeholders;
+use crate::tables::{read_records, records_to_table, sort_records, to_records, to_text};
+use crate::templates::{
+    ButtonTemplate, Card, CardsTemplate, InlineNotificationTemplate, ListTemplate, YouTubeTemplate,
+};
+use askama::Template;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+/// Variables that can be used in content as `{{ name }}`, page variables take precedence over
+/// site variables.
+pub(crate) struct Variables<'a> {
+    site: Option<&'a HashMap<String, String>>,
+    page: Option<&'a HashMap<String, String>>,
+}
+
+impl Variables<'_> {
+    pub(crate) fn new<'a>(
+        main_config: &'a MainConfig,
+        settings: Option<&'a ContentSettings>,
+    ) -> Variables<'a> {
+        Variables {
+            site: main_config.variables.as_ref(),
+            page: settings.and_then(|s| s.variables.as_ref()),
+        }
+    }
+    fn get(&self, name: &str) -> Option<&str> {
+        self.page
+            .and_then(|p| p.get(name))
+            .or_else(|| self.site.and_then(|s| s.get(name)))
+            .map(|v| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct ShortcodeError {
+    details: String,
+}
+
+impl Display for ShortcodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for ShortcodeError {}
+
+fn error<T>(details: String) -> Result<T, ShortcodeError> {
+    Err(ShortcodeError { details })
+}
+
+struct Shortcode {
+    name: String,
+    arguments: HashMap<String, String>,
+    body: Option<String>,
+    /// Whether the shortcode is on a line of its own, apart from the markers of lists and quotes.
+    own_line: bool,
+}
+
+impl Shortcode {
+    fn argument(&self, name: &str) -> Result<&str, ShortcodeError> {
+        match self.arguments.get(name) {
+            Some(v) => Ok(v),
+            None => Err(ShortcodeError {
+                details: format!("Shortcode {} is missing argument {}", self.name, name),
+            }),
+        }
+    }
+    fn color(&self, default: Color) -> Result<&'static str, ShortcodeError> {
+        match self.arguments.get("color") {
+            None => Ok(default.to_css_class()),
+            Some(c) => match Color::from_name(c) {
+                Some(color) => Ok(color.to_css_class()),
+                None => Err(ShortcodeError {
+                    details: format!("Shortcode {} has unknown color {}", self.name, c),
+                }),
+            },
+        }
+    }
+}
+
+/// Expands `{{ variable }}` placeholders and `{{< shortcode key="value" >}}` shortcodes, like
+/// `{{< include file="tips.md" >}}`. Paired shortcodes like `notification` take a body that ends
+/// with `{{< /notification >}}`. Code blocks and code spans are left alone, and `\{{` gives a
+/// literal `{{`. Problems are reported naming the origin, and leave the text as it was. The
+/// shortcodes are replaced by markers for the rendered html.
+pub(crate) fn expand(text: &str, context: &ContentContext, origin: &str) -> Placeholders {
+    let mut expanded = Placeholders::new("VADOSSHORTCODE", text.len());
+    let mut at_line_start = true;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(length) = code_length(rest, at_line_start) {
+            expanded.text.push_str(&rest[..length]);
+            at_line_start = rest[..length].ends_with('\n');
+            rest = &rest[length..];
+            continue;
+        }
+        at_line_start = c == '\n';
+        if let Some(after) = rest.strip_prefix("\\{{") {
+            expanded.text.push_str("{{");
+            rest = after;
+            continue;
+        }
+        if !rest.starts_with("{{") {
+            expanded.text.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if let Some(inner) = rest.strip_prefix("{{<") {
+            match parse_shortcode(inner) {
+                Ok((mut shortcode, consumed)) => {
+                    shortcode.own_line = on_own_line(&expanded.text, &rest[3 + consumed..]);
+                    match render_shortcode(&shortcode, context, origin) {
+                        Ok(html) => expanded.push(html, true),
+                        Err(e) => {
+                            println!("error: {} in {}", e, origin);
+                            expanded.text.push_str(&rest[..3 + consumed]);
+                        }
+                    }
+                    rest = &rest[3 + consumed..];
+                }
+                Err(e) => {
+                    println!("error: {} in {}", e, origin);
+                    expanded.text.push_str("{{<");
+                    rest = &rest[3..];
+                }
+            }
+        } else {
+            match rest.find("}}") {
+                None => {
+                    expanded.text.push_str(rest);
+                    rest = "";
+                }
+                Some(end) => {
+                    let name = rest[2..end].trim();
+                    match context.variables.get(name) {
+                        Some(value) => expanded.text.push_str(value),
+                        None => {
+                            println!("error: Unknown variable {} in {}", name, origin);
+                            expanded.text.push_str(&rest[..end + 2]);
+                        }
+                    }
+                    rest = &rest[end + 2..];
+                }
+            }
+        }
+    }
+    expanded.text.push_str(rest);
+    expanded
+}
+
+/// Parses a shortcode directly following `{{<`, returning it together with the number of bytes
+/// consumed, including the closing tag of a paired shortcode.
+fn parse_shortcode(text: &str) -> Result<(Shortcode, usize), ShortcodeError> {
+    let end = match text.find(">}}") {
+        None => {
+            return Err(ShortcodeError {
+                details: String::from("Shortcode is not closed with >}}"),
+            })
+        }
+        Some(e) => e,
+    };
+    let tag = text[..end].trim();
+    let (name, arguments) = match tag.split_once(char::is_whitespace) {
+        None => (tag, ""),
+        Some((n, a)) => (n, a),
+    };
+    let mut shortcode = Shortcode {
+        name: String::from(name),
+        arguments: parse_arguments(arguments)?,
+        body: None,
+        own_line: false,
+    };
+    let mut consumed = end + 3;
+    if is_paired(name) {
+        let after = &text[consumed..];
+        let closing = format!("{{{{< /{} >}}}}", name);
+        match after.find(&closing) {
+            None => {
+                return Err(ShortcodeError {
+                    details: format!("Shortcode {} is missing {}", name, closing),
+                })
+            }
+            Some(e) => {
+                shortcode.body = Some(String::from(&after[..e]));
+                consumed += e + closing.len();
+            }
+        }
+    }
+    Ok((shortcode, consumed))
+}
+
+fn parse_arguments(text: &str) -> Result<HashMap<String, String>, ShortcodeError> {
+    let mut result = HashMap::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let (key, value_part) = match rest.split_once("=\"") {
+            None => {
+                return Err(ShortcodeError {
+                    details: format!("Could not parse shortcode arguments {}", text),
+                })
+            }
+            Some(s) => s,
+        };
+        let (value, after) = match value_part.split_once('"') {
+            None => {
+                return Err(ShortcodeError {
+                    details: format!("Unclosed value in shortcode arguments {}", text),
+                })
+            }
+            Some(s) => s,
+        };
+        result.insert(String::from(key.trim()), String::from(value));
+        rest = after.trim_start();
+    }
+    Ok(result)
+}
+
+fn is_paired(name: &str) -> bool {
+    name == "notification"
+}
+
+fn render_shortcode(
+    shortcode: &Shortcode,
+    context: &ContentContext,
+    origin: &str,
+) -> Result<String, ShortcodeError> {
+    match shortcode.name.as_str() {
+        "youtube" => Ok(YouTubeTemplate {
+            id: shortcode.argument("id")?,
+            title: shortcode
+                .arguments
+                .get("title")
+                .map(|t| t.as_str())
+                .unwrap_or("YouTube video"),
+        }
+        .render()
+        .unwrap()),
+        "notification" => {
+            let body = shortcode.body.as_deref().unwrap_or("");
+            let expanded = expand(body, context, origin);
+            let content = expanded.restore(&markdown_to_html(&expanded.text, context));
+            Ok(InlineNotificationTemplate {
+                title: shortcode.arguments.get("title").map(|t| t.as_str()),
+                color: shortcode.color(Color::Info)?,
+                content: &content,
+            }
+            .render()
+            .unwrap())
+        }
+        "image" => {
+            let key = shortcode.argument("key")?;
+            match context.structure.process_image(key, ImageType::Content) {
+                Some(html) => Ok(html),
+                None => error(format!("Shortcode image refers to unknown image {}", key)),
+            }
+        }
+        "button" => {
+            let url = shortcode.argument("url")?;
+            Ok(ButtonTemplate {
+                url,
+                title: shortcode.argument("title")?,
+                color: shortcode.color(Color::Link)?,
+                icon: shortcode.arguments.get("icon").map(|i| i.as_str()),
+                external: !url.starts_with('/'),
+            }
+            .render()
+            .unwrap())
+        }
+        "include" => include(shortcode, context),
+        "table" => table(shortcode, context),
+        "data" => data(shortcode, context),
+        other => error(format!("Unknown shortcode {}", other)),
+    }
+}
+
+/// Finds an included file, relative to the page, or in the partials directory. Files starting with
+/// `/` are relative to the source.
+fn include_path(file: &str, context: &ContentContext) -> Option<String> {
+    let candidates = match file.strip_prefix('/') {
+        Some(f) => vec![format!("{}/{}", context.source, f)],
+        None => vec![
+            get_file_path(context.source, &context.directory, file),
+            format!("{}/{}/{}", context.source, PARTIALS_DIRECTORY, file),
+        ],
+    };
+    candidates.into_iter().find(|c| Path::new(c).is_file())
+}
+
+/// Whether the text before and after a shortcode on its line is empty, or only starts a list item or
+/// quote.
+fn on_own_line(before: &str, after: &str) -> bool {
+    let before = before.rsplit('\n').next().unwrap_or("");
+    let after = after.split('\n').next().unwrap_or("");
+    before
+        .chars()
+        .all(|c| " \t>-*+.)".contains(c) || c.is_ascii_digit())
+        && after.trim().is_empty()
+}
+
+/// Renders a markdown or html file into the content, includes can be nested, as long as a file
+/// doesn't include itself. Markdown renders to blocks, so it can only be included on a line of its
+/// own, not inside a paragraph.
+fn include(shortcode: &Shortcode, context: &ContentContext) -> Result<String, ShortcodeError> {
+    let file = shortcode.argument("file")?;
+    let is_markdown = match file {
+        md if md.ends_with(".md") => true,
+        html if html.ends_with(".html") => false,
+        _ => {
+            return error(format!(
+                "Can only include .md and .html files, not {}",
+                file
+            ))
+        }
+    };
+    let path = match include_path(file, context) {
+        None => {
+            return error(format!(
+                "Included file {} was not found relative to page {} or in {}",
+                file, context.path, PARTIALS_DIRECTORY
+            ))
+        }
+        Some(p) => p,
+    };
+    if is_markdown && !shortcode.own_line {
+        return error(format!(
+            "Markdown file {} can only be included on a line of its own",
+            file
+        ));
+    }
+    if context.includes.borrow().contains(&path) {
+        let mut cycle = context.includes.borrow().clone();
+        cycle.push(path);
+        return error(format!("Include cycle {}", cycle.join(" -> ")));
+    }
+    let text = match fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) => return error(format!("Could not read included file {}: {}", path, e)),
+    };
+    context.includes.borrow_mut().push(path.clone());
+    let expanded = expand(&text, context, &path);
+    let html = if is_markdown {
+        expanded.restore(&markdown_to_html(&expanded.text, context))
+    } else {
+        expanded.restore(&context.sanitize(&expanded.text))
+    };
+    context.includes.borrow_mut().pop();
+    Ok(html)
+}
+
+fn list_argument(value: &str) -> Vec<String> {
+    value.split(',').map(|v| String::from(v.trim())).collect()
+}
+
+/// The table settings of the page, overridden by the arguments. `columns="name,bpm"` and
+/// `labels="bpm:Tempo"` take comma separated lists, other arguments are only allowed if they are in
+/// `allowed`.
+fn table_config(
+    shortcode: &Shortcode,
+    context: &ContentContext,
+    allowed: &[&str],
+) -> Result<TableConfig, ShortcodeError> {
+    let mut config = context.table.cloned().unwrap_or_default();
+    for (key, value) in &shortcode.arguments {
+        match key.as_str() {
+            "columns" => config.columns = Some(list_argument(value)),
+            "labels" => {
+                let mut labels = HashMap::new();
+                for label in list_argument(value) {
+                    match label.split_once(':') {
+                        Some((c, l)) => labels.insert(String::from(c), String::from(l)),
+                        None => return error(format!("Label {} should be column:label", label)),
+                    };
+                }
+                config.labels = Some(labels)
+            }
+            "sort" => config.sort = Some(value.clone()),
+            "descending" => config.descending = Some(value == "true"),
+            "decimals" => match value.parse() {
+                Ok(d) => config.decimals = Some(d),
+                Err(_) => return error(format!("Decimals {} is not a number", value)),
+            },
+            other if allowed.contains(&other) => {}
+            other => {
+                return error(format!(
+                    "Unknown argument {} for shortcode {}",
+                    other, shortcode.name
+                ))
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// Renders a csv or json file, relative to the page, as table.
+fn table(shortcode: &Shortcode, context: &ContentContext) -> Result<String, ShortcodeError> {
+    let file = shortcode.argument("file")?;
+    let config = table_config(shortcode, context, &["file"])?;
+    let file_path = get_file_path(context.source, &context.directory, file);
+    match read_records(&file_path).and_then(|r| records_to_table(r, Some(&config))) {
+        Ok(html) => Ok(html),
+        Err(e) => error(format!("Could not render {} as table: {}", file_path, e)),
+    }
+}
+
+/// Replaces `{column}` in the template with the values of the row, missing values are left empty.
+fn fill(template: &str, row: &Map<String, Value>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            None => break,
+            Some(end) => {
+                let column = &rest[start + 1..start + end];
+                result.push_str(&row.get(column).map(to_text).unwrap_or_default());
+                rest = &rest[start + end + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Renders site data as a `list`, `table` or `cards`, chosen with `as`. The data is a dotted key
+/// in the data directory, like `source="tour.dates"`. Lists take an `item` template like
+/// `item="{song} ({bpm} bpm)"`, cards take the templates `title`, `subtitle`, `content`, `image`
+/// and `url`. All can be sorted like tables, and `limit` the number of records.
+fn data(shortcode: &Shortcode, context: &ContentContext) -> Result<String, ShortcodeError> {
+    let source = shortcode.argument("source")?;
+    let value = match context.structure.get_data(context.path, source) {
+        None => return error(format!("No site data found for {}", source)),
+        Some(v) => v.clone(),
+    };
+    let allowed = [
+        "source", "as", "limit", "item", "ordered", "title", "subtitle", "content", "image", "url",
+    ];
+    let config = table_config(shortcode, context, &allowed)?;
+    let mut records = match to_records(value).and_then(|mut r| {
+        sort_records(&mut r, Some(&config))?;
+        Ok(r)
+    }) {
+        Ok(r) => r,
+        Err(e) => return error(format!("Could not use site data {}: {}", source, e)),
+    };
+    if let Some(limit) = shortcode.arguments.get("limit") {
+        match limit.parse() {
+            Ok(l) => records.rows.truncate(l),
+            Err(_) => return error(format!("Limit {} is not a number", limit)),
+        }
+    }
+    let template = |name: &str| shortcode.arguments.get(name).map(|t| t.as_str());
+    match template("as").unwrap_or("list") {
+        "table" => match records_to_table(records, Some(&config)) {
+            Ok(html) => Ok(html),
+            Err(e) => error(format!("Could not render site data {}: {}", source, e)),
+        },
+        "list" => {
+            let first = records.columns.first().map(|c| format!("{{{}}}", c));
+            let item = template("item").or(first.as_deref()).unwrap_or("");
+            let items: Vec<String> = records.rows.iter().map(|r| fill(item, r)).collect();
+            Ok(ListTemplate {
+                ordered: template("ordered") == Some("true"),
+                items: &items,
+            }
+            .render()
+            .unwrap())
+        }
+        "cards" => {
+            let field = |name: &str, row: &Map<String, Value>| {
+                template(name)
+                    .map(|t| fill(t, row))
+                    .filter(|f| !f.is_empty())
+            };
+            let cards: Vec<Card> = records
+                .rows
+                .iter()
+                .map(|row| Card {
+                    title: field("title", row).unwrap_or_default(),
+                    subtitle: field("subtitle", row),
+                    content: field("content", row),
+                    image: field("image", row)
+                        .and_then(|i| context.structure.process_image(&i, ImageType::Sub)),
+                    url: field("url", row),
+                })
+                .collect();
+            Ok(CardsTemplate { cards: &cards }.render().unwrap())
+        }
+        other => error(format!(
+            "Unknown data style {}, use list, table or cards",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcodes_on_their_own_line() {
+        assert!(on_own_line("", ""));
+        assert!(on_own_line("intro\n", "\nmore"));
+        assert!(on_own_line("intro\n  ", "  \n"));
+        assert!(on_own_line("> ", ""));
+        assert!(on_own_line("- ", "\n"));
+        assert!(on_own_line("1. ", ""));
+    }
+
+    #[test]
+    fn shortcodes_inside_a_paragraph() {
+        assert!(!on_own_line("See ", " here."));
+        assert!(!on_own_line("intro\nSee ", ""));
+        assert!(!on_own_line("", " here.\n"));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::config_files::SortStrategy;
+use crate::structure::{Item, Structure};
+use crate::templates::{SiblingLink, SiblingsTemplate};
+use askama::Template;
+use std::sync::Arc;
+
+fn to_link(item: &Item, part: Option<usize>, structure: &Structure) -> SiblingLink {
+    let image = item
+        .image
+        .as_ref()
+        .and_then(|i| structure.get_processed_image(i));
+    SiblingLink {
+        title: item.title.clone(),
+        url: item.path.clone(),
+        part,
+        image: image.as_ref().map(|i| i.src.clone()),
+        alt: image.map(|i| i.alt.clone()).unwrap_or_default(),
+    }
+}
+
+/// Renders the links to the previous and next page in the sorted children of the parent. They're on
+/// by default, except for the pages at the top, which are in the main menu. Set on a section, it
+/// applies to the children that don't set it themselves. The children of a series are numbered.
+pub(crate) fn get_sibling_navigation(item: &Item, structure: &Structure) -> Option<String> {
+    let parent = structure.get_parent(&item.path)?;
+    let enabled = item
+        .sibling_navigation
+        .or(parent.sibling_navigation)
+        .unwrap_or(parent.path != "/");
+    if !enabled {
+        return None;
+    }
+    let mut siblings: Vec<Arc<Item>> = structure.get_children(&parent.path);
+    // pages sorted newest first are read from old to new, so the next page is the newer one
+    if structure.get_sort_strategy(&parent.path) == SortStrategy::DateDescending {
+        siblings.reverse()
+    }
+    let index = siblings.iter().position(|s| s.path == item.path)?;
+    let series = parent.series == Some(true);
+    let part = |i: usize| series.then_some(i + 1);
+    let previous = index
+        .checked_sub(1)
+        .map(|i| to_link(&siblings[i], part(i), structure));
+    let next = siblings
+        .get(index + 1)
+        .map(|s| to_link(s, part(index + 1), structure));
+    if previous.is_none() && next.is_none() {
+        return None;
+    }
+    let html = SiblingsTemplate {
+        previous,
+        next,
+        part: part(index).map(|p| (p, siblings.len())),
+    }
+    .render()
+    .unwrap();
+    Some(html)
+}
@@ -0,0 +1,81 @@
+const WORDS_PER_MINUTE: usize = 200;
+const SUMMARY_LENGTH: usize = 160;
+
+/// Numbers about the rendered content of a page, and a summary derived from it.
+#[derive(Debug)]
+pub(crate) struct ContentStats {
+    pub(crate) word_count: usize,
+    pub(crate) summary: Option<String>,
+}
+
+impl ContentStats {
+    pub(crate) fn new(html: &str) -> ContentStats {
+        ContentStats {
+            word_count: to_text(html).split_whitespace().count(),
+            summary: first_paragraph(html).map(|p| truncate(&p)),
+        }
+    }
+    /// The estimated reading time in minutes, if there is anything to read.
+    pub(crate) fn reading_time(&self) -> Option<usize> {
+        match self.word_count {
+            0 => None,
+            w => Some(w.div_ceil(WORDS_PER_MINUTE)),
+        }
+    }
+}
+
+/// Replaces tags with spaces, so words in different elements stay apart, and decodes the common
+/// entities.
+fn to_text(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                result.push(' ')
+            }
+            c if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    result
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn first_paragraph(html: &str) -> Option<String> {
+    let mut rest = html;
+    while let Some(start) = rest.find("<p>") {
+        rest = &rest[start + 3..];
+        let end = rest.find("</p>")?;
+        let text = to_text(&rest[..end]);
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !text.is_empty() {
+            return Some(text);
+        }
+        rest = &rest[end..];
+    }
+    None
+}
+
+/// Cuts the text at the last word that fits, preferring the end of a sentence.
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= SUMMARY_LENGTH {
+        return String::from(text);
+    }
+    let cut: String = text.chars().take(SUMMARY_LENGTH).collect();
+    if let Some(end) = cut.rfind(". ") {
+        if end > SUMMARY_LENGTH / 2 {
+            return String::from(&cut[..=end]);
+        }
+    }
+    match cut.rsplit_once(' ') {
+        None => format!("{}…", cut),
+        Some((words, _)) => format!("{}…", words.trim_end_matches([',', ';', ':'])),
+    }
+}
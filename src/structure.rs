@@ -1,11 +1,29 @@
+use crate::authors::Author;
 use crate::bulma::ImageType;
-use crate::config_files::{MenuConfig, PageConfig, RawMenuItem, RawSocialItem};
+use crate::config_files::{
+    ContentSettings, ListingConfig, MenuConfig, Notification, PageConfig, RawMenuItem,
+    RawSocialItem, SideNotifications, SortStrategy, SpecialPage,
+};
 use crate::content::{items_to_side_notifications, to_internal_image};
+use crate::data::SiteData;
+use crate::dates::Date;
+use crate::headings::HeadingIds;
 use crate::image::ProcessedImage;
-use crate::structure::SocialItem::{Facebook, Github, LinkedIn, Other, YouTube};
+use crate::related::related_items;
+use crate::stats::ContentStats;
+use crate::structure::SocialItem::{
+    Bandcamp, Bluesky, Email, Facebook, Github, Instagram, LinkedIn, Mastodon, Other, Rss,
+    SoundCloud, Spotify, Twitch, Twitter, YouTube,
+};
+use crate::taxonomies::{Taxonomy, Term, TAXONOMIES};
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use serde_json::Value;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 fn parent_path(path: &str) -> Option<String> {
@@ -14,7 +32,7 @@ fn parent_path(path: &str) -> Option<String> {
     }
     match String::from(path).rsplit_once('/') {
         None => Some(String::from("/")),
-        Some((first, _)) if first.is_empty() => Some(String::from("/")),
+        Some(("", _)) => Some(String::from("/")),
         Some((first, _)) => Some(String::from(first)),
     }
 }
@@ -29,11 +47,35 @@ pub(crate) struct Item {
     pub(crate) summary: Option<String>,
     pub(crate) content: String,
     pub(crate) order: u32,
+    pub(crate) date: Option<Date>,
+    pub(crate) updated: Option<Date>,
+    pub(crate) sort: Option<SortStrategy>,
+    pub(crate) sibling_navigation: Option<bool>,
+    pub(crate) series: Option<bool>,
+    pub(crate) side_notifications: Option<SideNotifications>,
+    pub(crate) special: Option<SpecialPage>,
+    pub(crate) content_settings: ContentSettings,
+    pub(crate) left_notifications: Option<Vec<Notification>>,
+    pub(crate) right_notifications: Option<Vec<Notification>>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) categories: Vec<String>,
+    pub(crate) authors: Vec<String>,
+    /// For the generated page of an author, which one it is.
+    pub(crate) author_page: Option<String>,
+    /// For the generated page of a tag or category, which one it is by slug.
+    pub(crate) taxonomy_term: Option<(Taxonomy, String)>,
+    pub(crate) listing: Option<ListingConfig>,
+    /// For the generated later pages of a listing, the section and the number of the page.
+    pub(crate) listing_page: Option<(String, usize)>,
+    /// The directory the content references are relative to, for generated pages this is the page
+    /// with the collection.
+    pub(crate) content_directory: String,
 }
 
 impl Item {
     pub(crate) fn new(path: String, page_config: PageConfig) -> Item {
         Item {
+            content_directory: path.clone(),
             path,
             title: page_config.title,
             sub_title: page_config.sub_title,
@@ -42,6 +84,37 @@ impl Item {
             summary: page_config.summary,
             content: page_config.content,
             order: page_config.order.unwrap_or(u32::MAX),
+            date: page_config.date,
+            updated: page_config.updated,
+            sort: page_config.sort,
+            sibling_navigation: page_config.sibling_navigation,
+            series: page_config.series,
+            side_notifications: page_config.side_notifications,
+            special: page_config.special,
+            content_settings: page_config.content_settings,
+            left_notifications: page_config.left_notifications,
+            right_notifications: page_config.right_notifications,
+            tags: page_config.tags.unwrap_or_default(),
+            categories: page_config.categories.unwrap_or_default(),
+            authors: page_config.authors.unwrap_or_default(),
+            author_page: None,
+            taxonomy_term: None,
+            listing: page_config.listing,
+            listing_page: None,
+        }
+    }
+    pub(crate) fn is_listed(&self) -> bool {
+        self.special.is_none()
+    }
+    pub(crate) fn is_not_found(&self) -> bool {
+        self.special == Some(SpecialPage::NotFound)
+    }
+    /// The path the html is written to, which differs from the source path for the 404 page.
+    pub(crate) fn output_path(&self) -> &str {
+        if self.is_not_found() {
+            "/404"
+        } else {
+            &self.path
         }
     }
 }
@@ -62,6 +135,33 @@ impl PartialOrd for Item {
     }
 }
 
+/// The strategy, or the default for the items when there is none.
+fn effective_strategy(items: &[Arc<Item>], strategy: Option<&SortStrategy>) -> SortStrategy {
+    match strategy {
+        Some(s) => s.clone(),
+        None if !items.is_empty() && items.iter().all(|i| i.date.is_some()) => {
+            SortStrategy::DateDescending
+        }
+        None => SortStrategy::Order,
+    }
+}
+
+/// Sorts the items on the strategy, or on the default for the items when there is none. Items
+/// without a date are put last when sorting on date.
+pub(crate) fn sort_items(items: &mut [Arc<Item>], strategy: Option<&SortStrategy>) {
+    let strategy = effective_strategy(items, strategy);
+    // the sorts are stable, so pages that are equal on the strategy stay sorted on order
+    items.sort();
+    match strategy {
+        SortStrategy::Order => {}
+        SortStrategy::Title => items.sort_by(|a, b| a.title.cmp(&b.title)),
+        SortStrategy::DateAscending => items.sort_by_key(|i| (i.date.is_none(), i.date)),
+        SortStrategy::DateDescending => {
+            items.sort_by_key(|i| (i.date.is_none(), i.date.map(std::cmp::Reverse)))
+        }
+    }
+}
+
 impl PartialEq for Item {
     fn eq(&self, other: &Self) -> bool {
         self.path == other.path
@@ -75,6 +175,17 @@ pub(crate) struct Structure {
     by_parent: DashMap<String, Vec<Arc<Item>>>,
     left_sub_notifications_cache: DashMap<String, Arc<Vec<String>>>,
     right_sub_notifications_cache: DashMap<String, Arc<Vec<String>>>,
+    content_stats: DashMap<String, Arc<ContentStats>>,
+    heading_ids: DashMap<String, HeadingIds>,
+    /// The pages by the slug of a term, as terms written differently can have the same url.
+    by_term: DashMap<(Taxonomy, String), Vec<Arc<Item>>>,
+    term_names: DashMap<(Taxonomy, String), BTreeSet<String>>,
+    listings: DashMap<String, Arc<String>>,
+    links: DashMap<String, BTreeSet<String>>,
+    authors: BTreeMap<String, Author>,
+    by_author: DashMap<String, Vec<Arc<Item>>>,
+    site_data: SiteData,
+    data_inputs: DashMap<String, BTreeSet<String>>,
 }
 
 impl Item {
@@ -108,34 +219,93 @@ impl Item {
 }
 
 impl RawMenuItem {
-    fn to_external_item(&self) -> MenuItem {
+    fn to_external_item(&self, url: &str, children: Option<Vec<MenuItem>>) -> MenuItem {
         MenuItem {
             menu_type: MenuType::External,
-            url: self.url.clone(),
+            url: String::from(url),
             title: self
                 .title
                 .as_ref()
                 .expect("External link should have a title")
                 .clone(),
             icon: self.icon.clone(),
-            children: None,
+            children,
+        }
+    }
+    fn to_header_item(&self, children: Option<Vec<MenuItem>>) -> MenuItem {
+        MenuItem {
+            menu_type: MenuType::Header,
+            url: String::new(),
+            title: self
+                .title
+                .as_ref()
+                .expect("Menu item without url should have a title")
+                .clone(),
+            icon: self.icon.clone(),
+            children,
         }
     }
 }
 
 impl Structure {
-    pub(crate) fn new(image_meta_cache: DashMap<String, Arc<ProcessedImage>>) -> Structure {
+    pub(crate) fn new(
+        image_meta_cache: DashMap<String, Arc<ProcessedImage>>,
+        site_data: SiteData,
+        authors: BTreeMap<String, Author>,
+    ) -> Structure {
         Structure {
             image_meta_cache,
             by_path: DashMap::new(),
             by_parent: DashMap::new(),
             left_sub_notifications_cache: DashMap::new(),
             right_sub_notifications_cache: DashMap::new(),
+            content_stats: DashMap::new(),
+            heading_ids: DashMap::new(),
+            by_term: DashMap::new(),
+            term_names: DashMap::new(),
+            listings: DashMap::new(),
+            links: DashMap::new(),
+            authors,
+            by_author: DashMap::new(),
+            site_data,
+            data_inputs: DashMap::new(),
         }
     }
     pub(crate) fn add_item(&self, item: Item) {
         let i = Arc::new(item);
+        if i.is_not_found() {
+            let other = self
+                .by_path
+                .iter()
+                .find(|e| e.value().is_not_found() && e.key() != &i.path)
+                .map(|e| e.key().clone());
+            if let Some(other) = other {
+                println!(
+                    "error: Pages {} and {} are both a NotFound page, only one is written to /404.html",
+                    other, i.path
+                );
+            }
+        }
         self.by_path.insert(i.path.clone(), i.clone());
+        if !i.is_listed() {
+            return;
+        }
+        for author in &i.authors {
+            self.by_author
+                .entry(author.clone())
+                .or_default()
+                .push(i.clone())
+        }
+        for taxonomy in TAXONOMIES {
+            for term in taxonomy.terms(&i) {
+                let key = (taxonomy, taxonomy.slug(term));
+                self.term_names
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(term.clone());
+                self.by_term.entry(key).or_default().push(i.clone())
+            }
+        }
         match parent_path(&i.path) {
             None => {}
             Some(p) => {
@@ -156,33 +326,194 @@ impl Structure {
         self.right_sub_notifications_cache
             .insert(String::from(path), Arc::new(notifications));
     }
+    pub(crate) fn add_listing(&self, path: &str, listing: String) {
+        self.listings.insert(String::from(path), Arc::new(listing));
+    }
+    pub(crate) fn get_listing(&self, path: &str) -> Option<Arc<String>> {
+        self.listings.get(path).map(|l| l.clone())
+    }
+    /// Records a link in the content of a page to another page.
+    pub(crate) fn add_link(&self, from: &str, to: &str) {
+        if from != to {
+            self.links
+                .entry(String::from(from))
+                .or_default()
+                .insert(String::from(to));
+        }
+    }
+    pub(crate) fn links_to(&self, from: &str, to: &str) -> bool {
+        self.links.get(from).is_some_and(|l| l.contains(to))
+    }
+    pub(crate) fn get_listed_items(&self) -> Vec<Arc<Item>> {
+        self.by_path
+            .iter()
+            .filter(|e| e.value().is_listed())
+            .map(|e| e.value().clone())
+            .collect()
+    }
+    /// The pages with a listing of their children.
+    pub(crate) fn get_listed_sections(&self) -> Vec<Arc<Item>> {
+        self.by_path
+            .iter()
+            .filter(|e| e.value().listing.is_some())
+            .map(|e| e.value().clone())
+            .collect()
+    }
+    pub(crate) fn get_parent(&self, path: &str) -> Option<Arc<Item>> {
+        parent_path(path).and_then(|p| self.by_path.get(&p).map(|i| i.clone()))
+    }
+    /// The listed children of the page, in the sorted order once the structure is sorted.
+    pub(crate) fn get_children(&self, path: &str) -> Vec<Arc<Item>> {
+        self.by_parent
+            .get(path)
+            .map(|c| c.clone())
+            .unwrap_or_default()
+    }
+    pub(crate) fn add_content_stats(&self, path: &str, stats: ContentStats) {
+        self.content_stats
+            .insert(String::from(path), Arc::new(stats));
+    }
+    pub(crate) fn add_heading_ids(&self, path: &str, ids: HeadingIds) {
+        self.heading_ids.insert(String::from(path), ids);
+    }
+    /// The heading ids used by the main content of the page.
+    pub(crate) fn get_heading_ids(&self, path: &str) -> HeadingIds {
+        self.heading_ids
+            .get(path)
+            .map(|i| i.clone())
+            .unwrap_or_default()
+    }
+    pub(crate) fn get_content_stats(&self, path: &str) -> Option<Arc<ContentStats>> {
+        self.content_stats.get(path).map(|s| s.clone())
+    }
+    /// The summary of the page, derived from the content when the page doesn't have one.
+    pub(crate) fn get_summary(&self, item: &Item) -> Option<String> {
+        item.summary.clone().or_else(|| {
+            self.get_content_stats(&item.path)
+                .and_then(|s| s.summary.clone())
+        })
+    }
+    /// Gets site data for a dotted key, the data files it's from are tracked as input of the page.
+    pub(crate) fn get_data(&self, path: &str, key: &str) -> Option<&Value> {
+        let (value, files) = self.site_data.get(key)?;
+        for file in files {
+            self.data_inputs
+                .entry(String::from(file))
+                .or_default()
+                .insert(String::from(path));
+        }
+        Some(value)
+    }
+    /// The pages using each data file.
+    pub(crate) fn get_data_inputs(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.data_inputs
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect()
+    }
     pub(crate) fn sort(&self) {
-        self.by_parent.iter_mut().for_each(|mut r| r.sort())
+        self.by_parent.iter_mut().for_each(|mut r| {
+            let strategy = self.by_path.get(r.key()).and_then(|p| p.sort.clone());
+            sort_items(r.value_mut(), strategy.as_ref())
+        });
+        self.by_term
+            .iter_mut()
+            .for_each(|mut r| sort_items(r.value_mut(), None));
+        self.by_author
+            .iter_mut()
+            .for_each(|mut r| sort_items(r.value_mut(), None))
+    }
+    /// The terms of the taxonomy with the number of pages that have them, sorted by slug.
+    pub(crate) fn get_terms(&self, taxonomy: Taxonomy) -> Vec<Term> {
+        let mut terms: Vec<Term> = self
+            .by_term
+            .iter()
+            .filter(|e| e.key().0 == taxonomy)
+            .map(|e| Term {
+                slug: e.key().1.clone(),
+                names: self
+                    .term_names
+                    .get(e.key())
+                    .map(|n| n.iter().cloned().collect())
+                    .unwrap_or_default(),
+                count: e.value().len(),
+            })
+            .collect();
+        terms.sort_by(|a, b| a.slug.cmp(&b.slug));
+        terms
+    }
+    pub(crate) fn get_term_items(&self, taxonomy: Taxonomy, slug: &str) -> Vec<Arc<Item>> {
+        self.by_term
+            .get(&(taxonomy, String::from(slug)))
+            .map(|i| i.clone())
+            .unwrap_or_default()
+    }
+    pub(crate) fn get_author(&self, id: &str) -> Option<&Author> {
+        self.authors.get(id)
+    }
+    pub(crate) fn get_authors(&self) -> impl Iterator<Item = &Author> {
+        self.authors.values()
+    }
+    pub(crate) fn get_author_items(&self, id: &str) -> Vec<Arc<Item>> {
+        self.by_author
+            .get(id)
+            .map(|i| i.clone())
+            .unwrap_or_default()
     }
     pub(crate) fn get_main_menu_items(&self, menu_config: &MenuConfig) -> Vec<MenuItem> {
-        let mut result = vec![];
-        for item in &menu_config.main_menu {
-            if item.url.starts_with("https://") {
-                result.push(item.to_external_item())
-            } else {
-                let i = self.by_path.get(&*item.url).unwrap();
-                let children = self.by_parent.get(&*item.url).map(|entry| {
-                    entry
-                        .value()
-                        .iter()
-                        .map(|i| i.to_side_menu_item(None))
-                        .collect()
+        menu_config
+            .main_menu
+            .iter()
+            .map(|item| self.to_menu_item(item))
+            .collect()
+    }
+    fn to_menu_item(&self, raw: &RawMenuItem) -> MenuItem {
+        if raw.divider == Some(true) {
+            return MenuItem::divider();
+        }
+        let children = raw
+            .children
+            .as_ref()
+            .map(|c| c.iter().map(|i| self.to_menu_item(i)).collect());
+        match &raw.url {
+            None => raw.to_header_item(children),
+            Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+                raw.to_external_item(url, children)
+            }
+            Some(url) => {
+                let i = self
+                    .by_path
+                    .get(&**url)
+                    .unwrap_or_else(|| panic!("Menu item refers to unknown page {}", url));
+                let children = children.or_else(|| {
+                    self.get_auto_children(
+                        url,
+                        raw.auto_children.unwrap_or(1),
+                        raw.max_children.unwrap_or(usize::MAX),
+                    )
                 });
-                result.push(i.to_main_menu_item(item.title.clone(), item.icon.clone(), children))
+                i.to_main_menu_item(raw.title.clone(), raw.icon.clone(), children)
             }
         }
-        result
+    }
+    fn get_auto_children(&self, path: &str, depth: usize, max: usize) -> Option<Vec<MenuItem>> {
+        if depth == 0 || max == 0 {
+            return None;
+        }
+        self.by_parent.get(path).map(|entry| {
+            entry
+                .value()
+                .iter()
+                .take(max)
+                .map(|i| i.to_side_menu_item(self.get_auto_children(&i.path, depth - 1, max)))
+                .collect()
+        })
     }
     pub(crate) fn get_side_menu_items(&self, path: &str) -> Option<MenuItem> {
         let depth = path.matches('/').count();
         match depth {
             d if d <= 2 => None,
-            d if d == 3 => self
+            3 => self
                 .by_parent
                 .get(path)
                 .map(|entry| {
@@ -236,8 +567,11 @@ impl Structure {
                             return Some(result);
                         }
                         Some(p) => {
-                            result.push(self.by_path.get(&*p).unwrap().to_side_menu_item(None));
-                            parent = parent_path(&*p)
+                            // generated pages, like those of a listing, can skip a level
+                            if let Some(i) = self.by_path.get(&*p) {
+                                result.push(i.to_side_menu_item(None));
+                            }
+                            parent = parent_path(&p)
                         }
                     }
                 }
@@ -276,23 +610,77 @@ impl Structure {
             }
         }
     }
-    pub(crate) fn get_side_notifications(&self, path: &str) -> Vec<String> {
-        let items = self
-            .by_parent
-            .get(path)
-            .unwrap_or_else(|| self.by_parent.get(&*parent_path(path).unwrap()).unwrap());
-        let mut result = vec![];
-        for item in items.value().iter().rev().take(4) {
-            if result.len() < 3 && item.path != path {
-                result.push(item.clone())
+    /// How the children of the page are sorted.
+    pub(crate) fn get_sort_strategy(&self, path: &str) -> SortStrategy {
+        let strategy = self.by_path.get(path).and_then(|i| i.sort.clone());
+        effective_strategy(&self.get_children(path), strategy.as_ref())
+    }
+    /// The last three children of the page, or of its parent when it has none, newest first when
+    /// sorted on date.
+    fn get_latest_items(&self, path: &str) -> Vec<Arc<Item>> {
+        let parent = match self.by_parent.contains_key(path) {
+            true => String::from(path),
+            false => match parent_path(path) {
+                None => return vec![],
+                Some(p) => p,
+            },
+        };
+        let items = self.get_children(&parent);
+        let others = |iter: &mut dyn Iterator<Item = &Arc<Item>>| -> Vec<Arc<Item>> {
+            iter.filter(|i| i.path != path).take(3).cloned().collect()
+        };
+        match self.get_sort_strategy(&parent) {
+            SortStrategy::DateDescending => others(&mut items.iter()),
+            SortStrategy::DateAscending => others(&mut items.iter().rev()),
+            SortStrategy::Order | SortStrategy::Title => {
+                let mut result = others(&mut items.iter().rev());
+                result.reverse();
+                result
+            }
+        }
+    }
+    /// The strategy of the page, or of the closest page above it that has one.
+    fn get_side_strategy(&self, path: &str) -> SideNotifications {
+        let mut current = Some(String::from(path));
+        while let Some(p) = current {
+            if let Some(s) = self
+                .by_path
+                .get(&p)
+                .and_then(|i| i.side_notifications.clone())
+            {
+                return s;
             }
+            current = parent_path(&p)
         }
-        result.reverse();
-        items_to_side_notifications(result, self)
+        SideNotifications::Latest
+    }
+    pub(crate) fn get_side_notifications(&self, path: &str) -> Vec<String> {
+        let result = match self.get_side_strategy(path) {
+            SideNotifications::Latest => self.get_latest_items(path),
+            SideNotifications::Related => related_items(&self.get_item(path), self, 3),
+            SideNotifications::Pages(paths) => paths
+                .iter()
+                .filter(|p| *p != path)
+                .filter_map(|p| match self.by_path.get(p) {
+                    Some(i) => Some(i.clone()),
+                    None => {
+                        println!(
+                            "error: Side notification refers to unknown page {} on page {}",
+                            p, path
+                        );
+                        None
+                    }
+                })
+                .collect(),
+        };
+        items_to_side_notifications(result, "sub-s", self)
     }
     pub(crate) fn get_menu_item(&self, path: &str) -> MenuItem {
         self.by_path.get(path).unwrap().to_side_menu_item(None)
     }
+    pub(crate) fn has_item(&self, path: &str) -> bool {
+        self.by_path.contains_key(path)
+    }
     pub(crate) fn get_item(&self, path: &str) -> Arc<Item> {
         self.by_path.get(path).unwrap().clone()
     }
@@ -302,12 +690,17 @@ impl Structure {
         image_type: ImageType,
     ) -> Option<String> {
         if let Some(p) = self.image_meta_cache.get(image_reference) {
-            Some(to_internal_image(p.clone(), image_type))
+            Some(to_internal_image(p.clone(), image_type, None))
         } else {
             println!("No image was found with reference {}.", image_reference);
             None
         }
     }
+    pub(crate) fn get_processed_image(&self, image_reference: &str) -> Option<Arc<ProcessedImage>> {
+        self.image_meta_cache
+            .get(image_reference)
+            .map(|p| p.value().clone())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -316,28 +709,85 @@ pub enum SocialItem {
     LinkedIn(String),
     Facebook(String),
     YouTube(String),
+    Mastodon(String),
+    Instagram(String),
+    Twitter(String),
+    Bluesky(String),
+    SoundCloud(String),
+    Spotify(String),
+    Bandcamp(String),
+    Twitch(String),
+    Email(String),
+    Rss(String),
     Other(String, String, String),
 }
 
+#[derive(Debug)]
+pub(crate) struct UnknownSocialError {
+    url: String,
+}
+
+impl Display for UnknownSocialError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Social link {} is not a known platform, please add an icon and color.",
+            self.url
+        )
+    }
+}
+
+impl Error for UnknownSocialError {}
+
+fn host(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let host = rest.split('/').next()?;
+    Some(host.strip_prefix("www.").unwrap_or(host))
+}
+
+fn is_feed(url: &str) -> bool {
+    let url = url.trim_end_matches('/');
+    url.ends_with(".rss")
+        || url.ends_with(".xml")
+        || url.ends_with(".atom")
+        || url.ends_with("/rss")
+        || url.ends_with("/feed")
+}
+
+/// Mastodon and most other fediverse servers use `https://<server>/@<user>` for profiles.
+fn is_fediverse(url: &str) -> bool {
+    match host(url) {
+        None => false,
+        Some(h) => url.contains(&format!("{}/@", h)),
+    }
+}
+
 impl SocialItem {
-    pub(crate) fn new(raw: &RawSocialItem) -> SocialItem {
-        match raw.url.clone() {
-            url if url.starts_with("https://github.com/") => Github(url),
-            url if url.starts_with("https://www.linkedin.com/") => LinkedIn(url),
-            url if url.starts_with("https://www.facebook.com/") => Facebook(url),
-            url if url.starts_with("https://www.youtube.com/") => YouTube(url),
-            url => {
-                let icon = raw
-                    .icon
-                    .as_ref()
-                    .expect("Other social links should have icon.");
-                let color = raw
-                    .color
-                    .as_ref()
-                    .expect("Other social links should have color.");
-                Other(url, icon.clone(), color.clone())
-            }
+    pub(crate) fn new(raw: &RawSocialItem) -> Result<SocialItem, UnknownSocialError> {
+        let url = raw.url.clone();
+        if let (Some(icon), Some(color)) = (&raw.icon, &raw.color) {
+            return Ok(Other(url, icon.clone(), color.clone()));
         }
+        let item = match host(&url) {
+            _ if url.starts_with("mailto:") => Email(url),
+            _ if is_feed(&url) => Rss(url),
+            Some("github.com") => Github(url),
+            Some("linkedin.com") => LinkedIn(url),
+            Some("facebook.com") => Facebook(url),
+            Some("youtube.com") => YouTube(url),
+            Some("instagram.com") => Instagram(url),
+            Some("twitter.com") | Some("x.com") => Twitter(url),
+            Some("bsky.app") => Bluesky(url),
+            Some("soundcloud.com") => SoundCloud(url),
+            Some("open.spotify.com") => Spotify(url),
+            Some(h) if h.ends_with("bandcamp.com") => Bandcamp(url),
+            Some("twitch.tv") => Twitch(url),
+            _ if is_fediverse(&url) => Mastodon(url),
+            _ => return Err(UnknownSocialError { url }),
+        };
+        Ok(item)
     }
     pub(crate) fn get_url(&self) -> &str {
         match self {
@@ -345,6 +795,16 @@ impl SocialItem {
             SocialItem::LinkedIn(url) => url,
             SocialItem::Facebook(url) => url,
             SocialItem::YouTube(url) => url,
+            SocialItem::Mastodon(url) => url,
+            SocialItem::Instagram(url) => url,
+            SocialItem::Twitter(url) => url,
+            SocialItem::Bluesky(url) => url,
+            SocialItem::SoundCloud(url) => url,
+            SocialItem::Spotify(url) => url,
+            SocialItem::Bandcamp(url) => url,
+            SocialItem::Twitch(url) => url,
+            SocialItem::Email(url) => url,
+            SocialItem::Rss(url) => url,
             SocialItem::Other(url, _, _) => url,
         }
     }
@@ -355,6 +815,16 @@ impl SocialItem {
             SocialItem::LinkedIn(_) => "linkedin",
             SocialItem::Facebook(_) => "facebook",
             SocialItem::YouTube(_) => "youtube",
+            SocialItem::Mastodon(_) => "mastodon",
+            SocialItem::Instagram(_) => "instagram",
+            SocialItem::Twitter(_) => "twitter",
+            SocialItem::Bluesky(_) => "butterfly",
+            SocialItem::SoundCloud(_) => "soundcloud",
+            SocialItem::Spotify(_) => "spotify",
+            SocialItem::Bandcamp(_) => "bandcamp",
+            SocialItem::Twitch(_) => "twitch",
+            SocialItem::Email(_) => "email",
+            SocialItem::Rss(_) => "rss",
             SocialItem::Other(_, icon, _) => icon,
         }
     }
@@ -365,6 +835,16 @@ impl SocialItem {
             SocialItem::LinkedIn(_) => "0077b5",
             SocialItem::Facebook(_) => "4267B2",
             SocialItem::YouTube(_) => "c4302b",
+            SocialItem::Mastodon(_) => "6364ff",
+            SocialItem::Instagram(_) => "e4405f",
+            SocialItem::Twitter(_) => "1da1f2",
+            SocialItem::Bluesky(_) => "0085ff",
+            SocialItem::SoundCloud(_) => "ff5500",
+            SocialItem::Spotify(_) => "1db954",
+            SocialItem::Bandcamp(_) => "629aa9",
+            SocialItem::Twitch(_) => "9146ff",
+            SocialItem::Email(_) => "4a4a4a",
+            SocialItem::Rss(_) => "ee802f",
             SocialItem::Other(_, _, color) => color,
         }
     }
@@ -374,6 +854,8 @@ impl SocialItem {
 pub enum MenuType {
     Internal,
     External,
+    Header,
+    Divider,
 }
 
 #[derive(Debug)]
@@ -384,3 +866,60 @@ pub(crate) struct MenuItem {
     pub(crate) icon: Option<String>,
     pub(crate) children: Option<Vec<MenuItem>>,
 }
+
+/// A single line in a navbar dropdown, nested menu groups are flattened and indented.
+pub(crate) struct DropdownEntry<'a> {
+    pub(crate) depth: usize,
+    pub(crate) item: &'a MenuItem,
+}
+
+impl DropdownEntry<'_> {
+    pub(crate) fn indent_class(&self) -> &'static str {
+        match self.depth {
+            0 => "",
+            1 => "pl-5",
+            _ => "pl-6",
+        }
+    }
+}
+
+impl MenuItem {
+    fn divider() -> MenuItem {
+        MenuItem {
+            menu_type: MenuType::Divider,
+            url: String::new(),
+            title: String::new(),
+            icon: None,
+            children: None,
+        }
+    }
+    pub(crate) fn is_active(&self, path: &str) -> bool {
+        match self.menu_type {
+            MenuType::Internal if self.url == "/" => path == "/",
+            MenuType::Internal => path.starts_with(&*self.url),
+            _ => false,
+        }
+    }
+    pub(crate) fn dropdown_entries(&self) -> Vec<DropdownEntry<'_>> {
+        let mut result = vec![];
+        if let Some(children) = &self.children {
+            for child in children {
+                push_dropdown_entries(&mut result, child, 0);
+            }
+        }
+        result
+    }
+}
+
+fn push_dropdown_entries<'a>(
+    result: &mut Vec<DropdownEntry<'a>>,
+    item: &'a MenuItem,
+    depth: usize,
+) {
+    result.push(DropdownEntry { depth, item });
+    if let Some(children) = &item.children {
+        for child in children {
+            push_dropdown_entries(result, child, depth + 1);
+        }
+    }
+}
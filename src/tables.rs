@@ -0,0 +1,328 @@
+use crate::config_files::TableConfig;
+use crate::templates::TableTemplate;
+use askama::Template;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+
+#[derive(Debug)]
+struct DataError {
+    details: String,
+}
+
+impl Display for DataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl Error for DataError {}
+
+const VALUE_COLUMN: &str = "value";
+
+/// Records read from a csv file or a json array of objects, keeping the order of the columns.
+pub(crate) struct Records {
+    pub(crate) columns: Vec<String>,
+    pub(crate) rows: Vec<Map<String, Value>>,
+}
+
+/// Fields in csv are kept as text, so values like `01` and `1.10` are shown as written. Empty fields
+/// are null.
+fn csv_value(field: &str) -> Value {
+    if field.is_empty() {
+        Value::Null
+    } else {
+        Value::String(String::from(field))
+    }
+}
+
+/// The number a value stands for, a json number or text that parses as one, like the fields of a
+/// csv file. Used for sorting, aligning and formatting, the text is still shown as is.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    }
+}
+
+pub(crate) fn read_csv(file_path: &str) -> Result<Records, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(file_path)?;
+    let columns: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let row = columns
+            .iter()
+            .zip(record.iter())
+            .map(|(c, f)| (c.clone(), csv_value(f)))
+            .collect();
+        rows.push(row);
+    }
+    Ok(Records { columns, rows })
+}
+
+/// Converts a json array of objects to records, the columns are in order of first appearance. A
+/// single object is a record on its own, other values are put in the `value` column.
+pub(crate) fn to_records(value: Value) -> Result<Records, Box<dyn Error>> {
+    let array = match value {
+        Value::Array(a) => a,
+        Value::Object(o) => vec![Value::Object(o)],
+        _ => {
+            return Err(Box::new(DataError {
+                details: String::from("data is not an array"),
+            }))
+        }
+    };
+    let mut columns: Vec<String> = vec![];
+    let mut rows = vec![];
+    for item in array {
+        match item {
+            Value::Object(row) => {
+                for key in row.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone())
+                    }
+                }
+                rows.push(row)
+            }
+            other => {
+                if !columns.iter().any(|c| c == VALUE_COLUMN) {
+                    columns.push(String::from(VALUE_COLUMN))
+                }
+                let mut row = Map::new();
+                row.insert(String::from(VALUE_COLUMN), other);
+                rows.push(row)
+            }
+        }
+    }
+    Ok(Records { columns, rows })
+}
+
+pub(crate) fn read_records(file_path: &str) -> Result<Records, Box<dyn Error>> {
+    if file_path.ends_with(".csv") {
+        read_csv(file_path)
+    } else {
+        to_records(serde_json::from_reader(File::open(file_path)?)?)
+    }
+}
+
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let a = a.filter(|v| !v.is_null());
+    let b = b.filter(|v| !v.is_null());
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => match (as_number(x), as_number(y)) {
+            (Some(m), Some(n)) => m.partial_cmp(&n).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => to_text(x).to_lowercase().cmp(&to_text(y).to_lowercase()),
+        },
+    }
+}
+
+pub(crate) fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A cell of a rendered table, numbers are aligned to the right.
+pub(crate) struct TableCell {
+    pub(crate) text: String,
+    pub(crate) numeric: bool,
+}
+
+fn to_cell(value: Option<&Value>, decimals: Option<usize>) -> TableCell {
+    let value = match value {
+        None => {
+            return TableCell {
+                text: String::new(),
+                numeric: false,
+            }
+        }
+        Some(v) => v,
+    };
+    match (as_number(value), decimals) {
+        (Some(n), Some(d)) => TableCell {
+            text: format!("{:.*}", d, n),
+            numeric: true,
+        },
+        (number, _) => TableCell {
+            text: to_text(value),
+            numeric: number.is_some(),
+        },
+    }
+}
+
+fn unknown_column(column: &str, records: &Records) -> Box<dyn Error> {
+    Box::new(DataError {
+        details: format!(
+            "unknown column {}, the columns are {}",
+            column,
+            records.columns.join(", ")
+        ),
+    })
+}
+
+/// Sorts the records on the column of the config, if there is one.
+pub(crate) fn sort_records(
+    records: &mut Records,
+    config: Option<&TableConfig>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(sort) = config.and_then(|c| c.sort.as_ref()) {
+        if !records.columns.contains(sort) {
+            return Err(unknown_column(sort, records));
+        }
+        records
+            .rows
+            .sort_by(|a, b| compare(a.get(sort), b.get(sort)));
+        if config.and_then(|c| c.descending).unwrap_or(false) {
+            records.rows.reverse()
+        }
+    }
+    Ok(())
+}
+
+/// Renders records as a Bulma table, with the columns, labels, sorting and number formatting of
+/// the config.
+pub(crate) fn records_to_table(
+    mut records: Records,
+    config: Option<&TableConfig>,
+) -> Result<String, Box<dyn Error>> {
+    let columns = match config.and_then(|c| c.columns.as_ref()) {
+        None => records.columns.clone(),
+        Some(selected) => {
+            if let Some(c) = selected.iter().find(|c| !records.columns.contains(c)) {
+                return Err(unknown_column(c, &records));
+            }
+            selected.clone()
+        }
+    };
+    sort_records(&mut records, config)?;
+    let decimals = config.and_then(|c| c.decimals);
+    let rows: Vec<Vec<TableCell>> = records
+        .rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|c| to_cell(row.get(c), decimals))
+                .collect()
+        })
+        .collect();
+    let headers: Vec<TableCell> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| TableCell {
+            text: config
+                .and_then(|c| c.labels.as_ref())
+                .and_then(|l| l.get(column))
+                .unwrap_or(column)
+                .clone(),
+            numeric: !rows.is_empty() && rows.iter().all(|r| r[i].numeric || r[i].text.is_empty()),
+        })
+        .collect();
+    Ok(TableTemplate {
+        headers: &headers,
+        rows: &rows,
+    }
+    .render()
+    .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn table_config(sort: &str, decimals: Option<usize>) -> TableConfig {
+        TableConfig {
+            columns: None,
+            labels: None,
+            sort: Some(String::from(sort)),
+            descending: None,
+            decimals,
+        }
+    }
+
+    #[test]
+    fn csv_value_keeps_the_text_of_numbers() {
+        for field in ["01", "0123", "1.10", "42", "-3", "abc"] {
+            assert_eq!(csv_value(field), Value::String(String::from(field)));
+        }
+        assert_eq!(csv_value(""), Value::Null);
+    }
+
+    #[test]
+    fn read_csv_keeps_the_columns_and_text() {
+        let path = std::env::temp_dir().join("vados_read_csv_test.csv");
+        fs::write(&path, "zip,version,name\n01234,1.10,a\n,2,b\n").unwrap();
+        let records = read_csv(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(records.columns, vec!["zip", "version", "name"]);
+        assert_eq!(records.rows[0]["zip"], Value::from("01234"));
+        assert_eq!(records.rows[0]["version"], Value::from("1.10"));
+        assert_eq!(records.rows[1]["zip"], Value::Null);
+    }
+
+    #[test]
+    fn cells_show_the_text_and_align_numbers() {
+        let cell = to_cell(Some(&Value::from("01")), None);
+        assert_eq!(cell.text, "01");
+        assert!(cell.numeric);
+        let cell = to_cell(Some(&Value::from("1.10")), None);
+        assert_eq!(cell.text, "1.10");
+        let cell = to_cell(Some(&Value::from("abc")), None);
+        assert_eq!(cell.text, "abc");
+        assert!(!cell.numeric);
+        let cell = to_cell(None, None);
+        assert_eq!(cell.text, "");
+        assert!(!cell.numeric);
+    }
+
+    #[test]
+    fn cells_format_numbers_with_decimals() {
+        assert_eq!(to_cell(Some(&Value::from("1.10")), Some(1)).text, "1.1");
+        assert_eq!(to_cell(Some(&Value::from("0123")), Some(2)).text, "123.00");
+        assert_eq!(to_cell(Some(&Value::from(2.5)), Some(0)).text, "2");
+        assert_eq!(to_cell(Some(&Value::from("abc")), Some(2)).text, "abc");
+    }
+
+    #[test]
+    fn sorting_compares_numbers_by_value() {
+        let mut records = Records {
+            columns: vec![String::from("n")],
+            rows: ["10", "9", "b", "", "0123", "a"]
+                .iter()
+                .map(|f| {
+                    let mut row = Map::new();
+                    row.insert(String::from("n"), csv_value(f));
+                    row
+                })
+                .collect(),
+        };
+        sort_records(&mut records, Some(&table_config("n", None))).unwrap();
+        let sorted: Vec<String> = records.rows.iter().map(|r| to_text(&r["n"])).collect();
+        assert_eq!(sorted, vec!["9", "10", "0123", "a", "b", ""]);
+    }
+
+    #[test]
+    fn tables_keep_leading_zeros() {
+        let records = Records {
+            columns: vec![String::from("zip")],
+            rows: vec![[(String::from("zip"), csv_value("01"))]
+                .into_iter()
+                .collect()],
+        };
+        let html = records_to_table(records, Some(&table_config("zip", None))).unwrap();
+        assert!(html.contains(">01<"), "{}", html);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::config_files::{PageConfig, SpecialPage};
+use crate::headings::slugify;
+use crate::structure::{Item, Structure};
+use crate::templates::TaxonomyTemplate;
+use askama::Template;
+
+/// The ways pages can be grouped besides the directories, each gets generated index pages.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub(crate) enum Taxonomy {
+    Categories,
+    Tags,
+}
+
+pub(crate) const TAXONOMIES: [Taxonomy; 2] = [Taxonomy::Categories, Taxonomy::Tags];
+
+impl Taxonomy {
+    fn name(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "categories",
+            Taxonomy::Tags => "tags",
+        }
+    }
+    fn title(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "Categories",
+            Taxonomy::Tags => "Tags",
+        }
+    }
+    fn singular(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "category",
+            Taxonomy::Tags => "tag",
+        }
+    }
+    fn color(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "is-info",
+            Taxonomy::Tags => "is-link is-light",
+        }
+    }
+    pub(crate) fn terms<'a>(&self, item: &'a Item) -> &'a [String] {
+        match self {
+            Taxonomy::Categories => &item.categories,
+            Taxonomy::Tags => &item.tags,
+        }
+    }
+    pub(crate) fn slug(&self, term: &str) -> String {
+        slugify(term)
+    }
+    pub(crate) fn url(&self, term: &str) -> String {
+        format!("/{}/{}", self.name(), self.slug(term))
+    }
+}
+
+/// A tag or category in use, with the ways it's written that have the same url.
+pub(crate) struct Term {
+    pub(crate) slug: String,
+    pub(crate) names: Vec<String>,
+    pub(crate) count: usize,
+}
+
+impl Term {
+    fn title(&self) -> String {
+        self.names.join(", ")
+    }
+}
+
+/// A link to the page of a tag or category.
+pub(crate) struct TagLink {
+    pub(crate) title: String,
+    pub(crate) url: String,
+    pub(crate) color: &'static str,
+    pub(crate) count: usize,
+}
+
+/// Links to the pages of the categories and tags of the item.
+pub(crate) fn tag_links(item: &Item) -> Vec<TagLink> {
+    let mut result = vec![];
+    for taxonomy in TAXONOMIES {
+        for term in taxonomy.terms(item) {
+            result.push(TagLink {
+                title: term.clone(),
+                url: taxonomy.url(term),
+                color: taxonomy.color(),
+                count: 0,
+            })
+        }
+    }
+    result
+}
+
+/// An unlisted page with raw html content, for pages generated from the structure.
+pub(crate) fn generated_item(
+    path: String,
+    title: String,
+    content: String,
+    structure: &Structure,
+) -> Item {
+    if structure.has_item(&path) {
+        println!("error: Page {} is also used for a generated page", path);
+    }
+    let mut page_config = PageConfig::new(&path);
+    page_config.title = title;
+    page_config.content = content;
+    page_config.special = Some(SpecialPage::Unlisted);
+    Item::new(path, page_config)
+}
+
+/// Creates the index page of every taxonomy in use, listing its terms, and a page for every term.
+/// The content is raw html, the pages of a term are added as notifications once their stats are known.
+pub(crate) fn taxonomy_items(structure: &Structure) -> Vec<Item> {
+    let mut result = vec![];
+    for taxonomy in TAXONOMIES {
+        let terms = structure.get_terms(taxonomy);
+        if terms.is_empty() {
+            continue;
+        }
+        for term in terms.iter().filter(|t| t.names.len() > 1) {
+            println!(
+                "error: The {} {} have the same url {}, their pages are combined",
+                taxonomy.name(),
+                term.title(),
+                taxonomy.url(&term.slug)
+            );
+        }
+        let links: Vec<TagLink> = terms
+            .iter()
+            .map(|term| TagLink {
+                title: term.title(),
+                url: taxonomy.url(&term.slug),
+                color: taxonomy.color(),
+                count: term.count,
+            })
+            .collect();
+        let content = TaxonomyTemplate {
+            description: None,
+            links: &links,
+        }
+        .render()
+        .unwrap()
+        .trim()
+        .to_string();
+        let path = format!("/{}", taxonomy.name());
+        result.push(generated_item(
+            path,
+            String::from(taxonomy.title()),
+            content,
+            structure,
+        ));
+        for term in terms {
+            let title = term.title();
+            let description = match term.count {
+                1 => format!("1 page with {} {}", taxonomy.singular(), title),
+                n => format!("{} pages with {} {}", n, taxonomy.singular(), title),
+            };
+            let content = TaxonomyTemplate {
+                description: Some(&description),
+                links: &[],
+            }
+            .render()
+            .unwrap()
+            .trim()
+            .to_string();
+            let mut item = generated_item(taxonomy.url(&term.slug), title, content, structure);
+            item.taxonomy_term = Some((taxonomy, term.slug));
+            result.push(item);
+        }
+    }
+    result
+}
@@ -1,6 +1,11 @@
+use crate::authors::Author;
 use crate::bulma::ImageType;
-use crate::structure::MenuType::Internal;
+use crate::dates::Date;
+use crate::headings::TocEntry;
+use crate::structure::MenuType::{Divider, Header, Internal};
 use crate::structure::{MenuItem, SocialItem};
+use crate::tables::TableCell;
+use crate::taxonomies::TagLink;
 use askama::Template;
 
 #[derive(Template)]
@@ -8,14 +13,21 @@ use askama::Template;
 pub(crate) struct PageTemplate<'a> {
     pub(crate) title: &'a str,
     pub(crate) summary: &'a Option<String>,
+    pub(crate) reading_time: Option<usize>,
+    pub(crate) no_index: bool,
     pub(crate) background_class: &'a str,
     pub(crate) navigation: &'a str,
     pub(crate) breadcrumbs: Option<String>,
     pub(crate) side_menu: Option<&'a String>,
+    pub(crate) toc: Option<&'a String>,
     pub(crate) main_content: &'a str,
+    pub(crate) listing: Option<&'a str>,
     pub(crate) left_sub_notifications: &'a Vec<String>,
     pub(crate) right_sub_notifications: &'a Vec<String>,
     pub(crate) side_notifications: &'a Vec<String>,
+    pub(crate) rel_me_links: &'a [String],
+    pub(crate) favicon: bool,
+    pub(crate) theme_color: &'a str,
     pub(crate) footer: &'a str,
     pub(crate) css_links: &'a [String],
     pub(crate) js_links: &'a [String],
@@ -51,8 +63,15 @@ pub(crate) struct SideMenuTemplate<'a> {
 pub(crate) struct ContentTemplate<'a> {
     pub(crate) title: &'a str,
     pub(crate) sub_title: &'a Option<String>,
+    pub(crate) tags: &'a [TagLink],
+    pub(crate) byline: Option<String>,
+    pub(crate) date: Option<Date>,
+    pub(crate) updated: Option<Date>,
+    pub(crate) reading_time: Option<usize>,
     pub(crate) image: Option<String>,
+    pub(crate) toc: Option<String>,
     pub(crate) content: String,
+    pub(crate) siblings: Option<String>,
 }
 
 #[derive(Template)]
@@ -60,10 +79,13 @@ pub(crate) struct ContentTemplate<'a> {
 pub(crate) struct InternalNotificationTemplate<'a> {
     pub(crate) title: &'a Option<String>,
     pub(crate) sub_title: &'a Option<String>,
+    pub(crate) date: Option<Date>,
+    pub(crate) reading_time: Option<usize>,
     pub(crate) color: &'a str,
     pub(crate) url: &'a str,
     pub(crate) image: Option<String>,
     pub(crate) content: Option<String>,
+    pub(crate) summary: Option<&'a str>,
     pub(crate) id: String,
 }
 
@@ -104,3 +126,122 @@ pub(crate) struct InternalImageTemplate<'a> {
     pub(crate) srcset: &'a str,
     pub(crate) alt: &'a str,
 }
+
+#[derive(Template)]
+#[template(path = "youtube.html")]
+pub(crate) struct YouTubeTemplate<'a> {
+    pub(crate) id: &'a str,
+    pub(crate) title: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "inline_notification.html")]
+pub(crate) struct InlineNotificationTemplate<'a> {
+    pub(crate) title: Option<&'a str>,
+    pub(crate) color: &'a str,
+    pub(crate) content: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "button.html")]
+pub(crate) struct ButtonTemplate<'a> {
+    pub(crate) url: &'a str,
+    pub(crate) title: &'a str,
+    pub(crate) color: &'a str,
+    pub(crate) icon: Option<&'a str>,
+    pub(crate) external: bool,
+}
+
+#[derive(Template)]
+#[template(path = "toc.html")]
+pub(crate) struct TableOfContentsTemplate<'a> {
+    pub(crate) entries: &'a [TocEntry],
+}
+
+#[derive(Template)]
+#[template(path = "admonition.html")]
+pub(crate) struct AdmonitionTemplate<'a> {
+    pub(crate) notification: bool,
+    pub(crate) color: &'a str,
+    pub(crate) icon: Option<&'a str>,
+    pub(crate) title: Option<&'a str>,
+    pub(crate) content: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "table.html")]
+pub(crate) struct TableTemplate<'a> {
+    pub(crate) headers: &'a [TableCell],
+    pub(crate) rows: &'a [Vec<TableCell>],
+}
+
+#[derive(Template)]
+#[template(path = "list.html")]
+pub(crate) struct ListTemplate<'a> {
+    pub(crate) ordered: bool,
+    pub(crate) items: &'a [String],
+}
+
+pub(crate) struct Card {
+    pub(crate) title: String,
+    pub(crate) subtitle: Option<String>,
+    pub(crate) content: Option<String>,
+    pub(crate) image: Option<String>,
+    pub(crate) url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "cards.html")]
+pub(crate) struct CardsTemplate<'a> {
+    pub(crate) cards: &'a [Card],
+}
+
+#[derive(Template)]
+#[template(path = "taxonomy.html")]
+pub(crate) struct TaxonomyTemplate<'a> {
+    pub(crate) description: Option<&'a str>,
+    pub(crate) links: &'a [TagLink],
+}
+
+/// A link to one of the pages of a listing.
+pub(crate) struct PageLink {
+    pub(crate) number: usize,
+    pub(crate) url: String,
+    pub(crate) current: bool,
+}
+
+#[derive(Template)]
+#[template(path = "listing.html")]
+pub(crate) struct ListingTemplate<'a> {
+    /// The entries rendered as cards, when the listing uses them.
+    pub(crate) cards: Option<String>,
+    pub(crate) entries: &'a [Card],
+    pub(crate) previous: Option<String>,
+    pub(crate) next: Option<String>,
+    pub(crate) pages: &'a [PageLink],
+}
+
+/// A link to a sibling page, with the source of the smallest variant of its image.
+pub(crate) struct SiblingLink {
+    pub(crate) title: String,
+    pub(crate) url: String,
+    pub(crate) part: Option<usize>,
+    pub(crate) image: Option<String>,
+    pub(crate) alt: String,
+}
+
+#[derive(Template)]
+#[template(path = "siblings.html")]
+pub(crate) struct SiblingsTemplate {
+    pub(crate) previous: Option<SiblingLink>,
+    pub(crate) next: Option<SiblingLink>,
+    pub(crate) part: Option<(usize, usize)>,
+}
+
+/// The authors with the source of their avatar as media objects, a profile shows the bio instead of linking to the profile page.
+#[derive(Template)]
+#[template(path = "authors.html")]
+pub(crate) struct AuthorsTemplate<'a> {
+    pub(crate) authors: &'a [(&'a Author, Option<String>)],
+    pub(crate) profile: bool,
+}
@@ -0,0 +1,181 @@
+use crate::files::written_files;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+const ATTRIBUTES: [&str; 3] = ["href", "src", "srcset"];
+
+/// The references and ids found in a single written html file.
+struct HtmlFile {
+    references: Vec<String>,
+    ids: HashSet<String>,
+}
+
+/// Returns the values of all occurrences of the attribute in the html.
+fn attribute_values<'a>(html: &'a str, attribute: &str) -> Vec<&'a str> {
+    let pattern = format!("{}=\"", attribute);
+    let mut result = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find(&pattern) {
+        let is_attribute = rest[..start]
+            .chars()
+            .last()
+            .is_some_and(char::is_whitespace);
+        rest = &rest[start + pattern.len()..];
+        if let Some(end) = rest.find('"') {
+            if is_attribute {
+                result.push(&rest[..end]);
+            }
+            rest = &rest[end..];
+        }
+    }
+    result
+}
+
+fn parse_html(html: &str) -> HtmlFile {
+    let mut references = vec![];
+    for attribute in ATTRIBUTES {
+        for value in attribute_values(html, attribute) {
+            let value = value.replace("&amp;", "&");
+            if attribute == "srcset" {
+                for candidate in value.split(',') {
+                    if let Some(url) = candidate.split_whitespace().next() {
+                        references.push(String::from(url))
+                    }
+                }
+            } else {
+                references.push(value)
+            }
+        }
+    }
+    let ids = attribute_values(html, "id")
+        .into_iter()
+        .map(String::from)
+        .collect();
+    HtmlFile { references, ids }
+}
+
+fn is_external(url: &str) -> bool {
+    url.starts_with("//")
+        || url
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains('/'))
+}
+
+/// The url the file is served at, `/lessons/scales.html` is served as `/lessons/scales`.
+fn page_url(file: &str) -> String {
+    match file.strip_suffix("index.html") {
+        Some(dir) => String::from(dir),
+        None => String::from(file.strip_suffix(".html").unwrap_or(file)),
+    }
+}
+
+/// Resolves the path of a local url against the url of the page it is on.
+fn resolve_path(path: &str, page: &str) -> String {
+    let joined = if path.starts_with('/') {
+        String::from(path)
+    } else {
+        let dir = page.rsplit_once('/').map(|(d, _)| d).unwrap_or("");
+        format!("{}/{}", dir, path)
+    };
+    let mut segments: Vec<&str> = vec![];
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    let trailing = if joined.ends_with('/') && !segments.is_empty() {
+        "/"
+    } else {
+        ""
+    };
+    format!("/{}{}", segments.join("/"), trailing)
+}
+
+/// Finds the written file a path refers to, the same way a static file server would.
+fn find_file(written: &BTreeSet<String>, path: &str) -> Option<String> {
+    let candidates = if path.ends_with('/') {
+        vec![format!("{}index.html", path)]
+    } else {
+        vec![
+            String::from(path),
+            format!("{}.html", path),
+            format!("{}/index.html", path),
+        ]
+    };
+    candidates.into_iter().find(|c| written.contains(c))
+}
+
+/// Checks the html files written to the destination in this build. Every local `href`, `src` and
+/// `srcset` candidate should point to a file of this build, and fragments to an id in that file, so
+/// files left by earlier builds don't hide dangling references. Dangling references are reported,
+/// external urls are listed but not fetched.
+pub(crate) fn verify_output(destination: &str) {
+    let written = written_files();
+    let mut files = HashMap::new();
+    for file in written.iter().filter(|f| f.ends_with(".html")) {
+        let path = Path::new(destination).join(file.trim_start_matches('/'));
+        match fs::read_to_string(&path) {
+            Ok(html) => {
+                files.insert(file.clone(), parse_html(&html));
+            }
+            Err(e) => println!(
+                "error: could not read {} to verify it: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    let mut file_names: Vec<&String> = files.keys().collect();
+    file_names.sort();
+    let mut external = BTreeMap::new();
+    let mut dangling = 0;
+    for file in file_names {
+        let page = page_url(file);
+        for reference in &files[file].references {
+            if is_external(reference) {
+                if reference.starts_with("http") || reference.starts_with("//") {
+                    *external.entry(reference.as_str()).or_insert(0) += 1;
+                }
+                continue;
+            }
+            let (rest, fragment) = match reference.split_once('#') {
+                None => (reference.as_str(), None),
+                Some((r, f)) => (r, Some(f)),
+            };
+            let path = rest.split('?').next().unwrap_or("");
+            let target = if path.is_empty() {
+                Some(file.clone())
+            } else {
+                find_file(&written, &resolve_path(path, &page))
+            };
+            match (target, fragment) {
+                (None, _) => {
+                    dangling += 1;
+                    println!("error: Dangling reference {} in {}", reference, file)
+                }
+                (Some(t), Some(f)) if !f.is_empty() && t.ends_with(".html") => {
+                    let found = files.get(&t).is_some_and(|h| h.ids.contains(f));
+                    if !found {
+                        dangling += 1;
+                        println!("error: Missing fragment {} in {}", reference, file)
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if dangling == 0 {
+        println!("All local references in {} html files resolve", files.len());
+    }
+    if !external.is_empty() {
+        println!("External urls, not checked:");
+        for (url, count) in external {
+            println!("  {} ({}x)", url, count);
+        }
+    }
+}